@@ -12,21 +12,62 @@ pub enum FilmwebErrors {
     InvalidYear { title_id: u32, failed_year: String },
     #[error("invalid credentials")]
     InvalidCredentials,
-    #[error("while sending a request / building a client / parsing a response: {}", .source)]
-    ReqwestError {
+    #[error("Filmweb asks to solve a captcha, log in with a browser and use its cookies instead")]
+    CaptchaRequired,
+    #[error("the account requires a two-factor authentication code, log in with a browser and use its cookies instead")]
+    TwoFactorRequired,
+    #[error("profile of {} doesn't exist or isn't public", .username)]
+    PrivateProfile { username: String },
+    #[error("while sending a request: {}", .source)]
+    TransportError {
         #[from]
-        source: reqwest::Error,
-    },
-    #[error("while inserting a cookie to a header: {}", .source)]
-    InvalidHeaderValue {
-        #[from]
-        source: reqwest::header::InvalidHeaderValue,
+        source: TransportError,
     },
     #[error("while probably trying to convert an id string to int: {}", .source)]
     InvalidId {
         #[from]
         source: std::num::ParseIntError,
     },
+    #[error("Filmweb API has changed. Update or wait for an update: {}", .source)]
+    FilmwebJsonApiChanged {
+        #[from]
+        source: serde_json::Error,
+    },
+    #[error("while building a client: {}", .source)]
+    BuildError {
+        #[from]
+        source: BuildError,
+    },
+    #[error("while importing cookies: {}", .source)]
+    CookieImportError {
+        #[from]
+        source: CookieImportError,
+    },
+    #[error("while accessing stored credentials: {}", .source)]
+    CredentialStoreError {
+        #[from]
+        source: CredentialStoreError,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum BuildError {
+    #[error("{} is not a valid base url: {}", .url, .source)]
+    InvalidBaseUrl {
+        url: String,
+        source: url::ParseError,
+    },
+    #[error("{} can't be used as a base url", .url)]
+    NotABaseUrl { url: String },
+    #[error("header {} has an invalid name or value", .name)]
+    InvalidHeader { name: String },
+    #[error("no PEM encoded certificate found")]
+    InvalidCertificate,
+    #[error("while building a client: {}", .source)]
+    ClientError {
+        #[from]
+        source: reqwest::Error,
+    },
 }
 
 #[derive(Error, Debug)]
@@ -34,7 +75,7 @@ pub enum FilmwebScrapeError {
     #[error("failed sending a request: {}", .source)]
     NetworkError {
         #[from]
-        source: reqwest::Error,
+        source: TransportError,
     },
     #[error("Filmweb API has changed. Update or wait for an update")]
     FilmwebJsonApiChanged {
@@ -52,7 +93,7 @@ pub enum IMDbScrapeError {
     #[error("failed sending a request: {}", .source)]
     NetworkError {
         #[from]
-        source: reqwest::Error,
+        source: TransportError,
     },
     #[error("Filmed crate is outdated. Update or wait for an update")]
     IrrecoverableOutdated,
@@ -70,6 +111,101 @@ pub enum IMDbScrapeError {
     GenreParseError { bad_title_url: String },
 }
 
+/// Error of a [`Transport`](crate::http::Transport), whichever HTTP stack is behind it
+#[derive(Error, Debug)]
+pub enum TransportError {
+    #[error("couldn't connect to {}: {}", .url, .source)]
+    Connect {
+        url: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    #[error("request to {} timed out: {}", .url, .source)]
+    Timeout {
+        url: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    #[error("request to {} failed: {}", .url, .source)]
+    Request {
+        url: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    #[error("{} {} isn't in the cassette", .method, .url)]
+    NotRecorded { method: &'static str, url: String },
+    #[error("{} answered with status {} after {} attempt(s)", .url, .status, .attempts)]
+    Status {
+        url: String,
+        status: u16,
+        attempts: u32,
+    },
+    #[error("gave up after {} attempt(s): {}", .attempts, .source)]
+    RetriesExhausted {
+        attempts: u32,
+        source: Box<TransportError>,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum CassetteError {
+    #[error("while accessing cassette {}: {}", .path.display(), .source)]
+    Io {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("cassette {} isn't valid: {}", .path.display(), .source)]
+    Json {
+        path: std::path::PathBuf,
+        source: serde_json::Error,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum CookieImportError {
+    #[error("while reading cookies from {}: {}", .path.display(), .source)]
+    Io {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("cookies export isn't valid JSON: {}", .source)]
+    Json {
+        #[from]
+        source: serde_json::Error,
+    },
+    #[error("filmweb.pl cookies are missing: {:?}, expired: {:?}", .missing, .expired)]
+    Unusable {
+        missing: Vec<&'static str>,
+        expired: Vec<&'static str>,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum SessionError {
+    #[error("while accessing session file {}: {}", .path.display(), .source)]
+    Io {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("session file {} isn't valid: {}", .path.display(), .source)]
+    Json {
+        path: std::path::PathBuf,
+        source: serde_json::Error,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum CredentialStoreError {
+    #[error("no credentials stored for account {}", .account)]
+    NotFound { account: String },
+    #[error("while accessing credential store {}: {}", .path.display(), .source)]
+    Io {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("credential store {} is corrupted", .path.display())]
+    Corrupted { path: std::path::PathBuf },
+    #[error("wrong passphrase for credential store {}", .path.display())]
+    WrongPassphrase { path: std::path::PathBuf },
+}
+
 #[derive(Error, Debug, PartialEq, Eq)]
 #[error("Failed parsing year: {}", .year_str)]
 pub struct ParseYearError {
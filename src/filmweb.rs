@@ -1,21 +1,45 @@
+mod accounts;
 pub mod auth;
+mod builder;
+mod cookie_import;
+mod credentials;
 mod json;
+mod jwt;
+mod library;
+mod login;
+mod public;
 pub mod query;
+mod session;
 mod utils;
 
 use crate::error::{FilmwebScrapeError, ParseGenreError};
+#[cfg(feature = "async")]
+use crate::http::AsyncTransport;
+use crate::http::{Metrics, MetricsSnapshot, Transport, TransportError};
 use crate::imdb::IMDb;
-use crate::utils::create_client;
 use crate::{
     imdb, AlternateTitle, AlternateTitles, FilmwebErrors, Genre, IMDbLookup, Title, TitleID,
-    TitleType, Year, USER_AGENT,
+    TitleType, Year,
 };
-pub use auth::FilmwebUser;
+pub use accounts::{AccountResults, FilmwebAccounts};
+#[cfg(feature = "async")]
+pub use auth::AsyncFilmwebUser;
+pub use auth::{letterboxd_writer, FilmwebUser, RatingComment};
+pub use builder::{FilmwebBuilder, FilmwebUserBuilder};
+pub use credentials::CredentialStore;
+#[cfg(feature = "encrypted-store")]
+pub use credentials::EncryptedFileStore;
+pub use jwt::JwtClaims;
+pub use library::{RatedTitles, WatchlistOrder};
+#[cfg(feature = "async")]
+pub use public::AsyncPublicFilmwebUser;
+pub use public::PublicFilmwebUser;
 pub use query::{Query, QueryBuilder};
-use utils::{parse_my_votebox, ScrapedFilmwebTitleData};
+pub use session::FilmwebSession;
+use utils::{parse_duration, parse_user_page, ScrapedFilmwebTitleData};
 
 use std::collections::HashMap;
-use std::ops::Deref;
+use std::sync::Arc;
 
 use json::{Preview, SearchResults, Type};
 use lazy_static::lazy_static;
@@ -23,7 +47,6 @@ use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 use once_cell::sync::OnceCell;
 use priority_queue::PriorityQueue;
-use reqwest::blocking::Client;
 use scraper::{Html, Selector};
 
 /// Enum containing all genres that occur on Filmweb
@@ -233,80 +256,82 @@ impl From<String> for FilmwebGenre {
     }
 }
 
-impl Deref for Filmweb {
-    type Target = Client;
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
-
 impl Default for Filmweb {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Origin of every Filmweb request, unless changed with a [`FilmwebBuilder`]
+pub const FILMWEB_BASE_URL: &str = "https://www.filmweb.pl";
+
 /// Struct containing methods to query Filmweb
-pub struct Filmweb(Client);
+pub struct Filmweb {
+    transport: Arc<dyn Transport>,
+    base_url: String,
+    metrics: Metrics,
+}
 
 impl Filmweb {
     /// Returns a Filmweb struct to query Filmweb
     #[must_use]
     pub fn new() -> Self {
-        let http_client = create_client().expect("Can create a client");
-        Self(http_client)
+        Self::builder().build().expect("Can create a client")
+    }
+
+    /// Returns a builder to configure Filmweb, e.g. to query a mock server or a proxy
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use filmed::filmweb::Filmweb;
+    /// let fw = Filmweb::builder()
+    ///     .base_url("http://127.0.0.1:8080")
+    ///     .build()?;
+    /// assert_eq!(fw.base_url(), "http://127.0.0.1:8080");
+    /// #    Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn builder() -> FilmwebBuilder {
+        FilmwebBuilder::new()
+    }
+
+    /// Origin that all requests are sent to
+    #[must_use]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Requests sent so far, per endpoint
+    #[must_use]
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
     }
 
     fn scrape_from_api(&self, api_url: &str) -> Result<Vec<FilmwebTitle>, FilmwebScrapeError> {
         log::trace!(target: "film_events", "api_url: {:?}", api_url);
 
         let mut found_titles: Vec<FilmwebTitle> = Vec::new();
-        let search_results: SearchResults = {
-            let res = self.get(api_url).send()?.text()?;
-            serde_json::from_str(&res).unwrap()
-        };
+        let search_results = parse_search_results(&self.transport.get(api_url)?.text())?;
 
         for hit in search_results.search_hits {
-            if let Type::Film | Type::Serial = hit.hit_type {
-                let (title_type_str, title_type) = match hit.hit_type {
-                    Type::Film => ("film", TitleType::Movie),
-                    Type::Serial => ("film", TitleType::Show),
-                    _ => panic!("Shouldn't be possible"),
-                };
-
-                let film_preview_req_url = format!(
-                    "https://www.filmweb.pl/api/v1/{title_type_str}/{}/preview",
-                    hit.id
-                );
-                let film_preview_res = self.get(film_preview_req_url).send()?.text()?;
+            if let Some((title_type_str, title_type)) = hit_kind(&hit.hit_type) {
+                let film_preview_res = self
+                    .transport
+                    .get(&preview_url(&self.base_url, title_type_str, hit.id))?
+                    .text();
                 let preview_result: Preview = serde_json::from_str(&film_preview_res)?;
-                let year = preview_result.year;
-                let name = preview_result
-                    .title
-                    .map(|title| title.title)
-                    .or_else(|| Some(preview_result.original_title.unwrap().title))
-                    .expect("it'll always be some");
-                let genres: Vec<FilmwebGenre> = preview_result
-                    .genres
-                    .into_iter()
-                    .map(|genre| FilmwebGenre::from_u8(genre.id).unwrap())
-                    .collect();
-                let title_url = format!(
-                    "https://www.filmweb.pl/{title_type_str}/{name}-{year}-{}",
-                    hit.id
-                );
-                let title = FilmwebTitle {
-                    alter_titles: AlternateTitle::fw_get_titles(&title_url, &self.0).ok(),
-                    name,
-                    fw_genres: genres,
-                    genres: OnceCell::new(),
-                    id: TitleID::FilmwebID(hit.id),
-                    year: year.into(),
-                    duration: Some(preview_result.duration),
+                let mut title = FilmwebTitle::from_preview(
+                    &self.base_url,
+                    hit.id,
+                    title_type_str,
                     title_type,
-                    imdb_data: None,
-                    url: title_url,
-                };
+                    preview_result,
+                );
+                title.lazy = Some(LazyTransport::Blocking(Arc::clone(&self.transport)));
                 found_titles.push(title);
             }
         }
@@ -317,7 +342,7 @@ impl Filmweb {
     ///
     /// # Examples
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// # use std::error::Error;
     /// #
     /// # fn main() -> Result<(), Box<dyn Error>> {
@@ -344,11 +369,118 @@ impl Filmweb {
         query: &Query,
         page: u16,
     ) -> Result<Vec<FilmwebTitle>, FilmwebScrapeError> {
-        let url = query.url(page);
+        let url = query.url_on(&self.base_url, page);
         self.scrape_from_api(&url)
     }
 }
 
+/// Async flavour of [`Filmweb`], sharing its parsing code
+#[cfg(feature = "async")]
+pub struct AsyncFilmweb {
+    transport: Arc<dyn AsyncTransport>,
+    base_url: String,
+    metrics: Metrics,
+}
+
+#[cfg(feature = "async")]
+impl Default for AsyncFilmweb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncFilmweb {
+    /// Returns an `AsyncFilmweb` struct to query Filmweb
+    #[must_use]
+    pub fn new() -> Self {
+        FilmwebBuilder::new()
+            .build_async()
+            .expect("Can create a client")
+    }
+
+    /// Origin that all requests are sent to
+    #[must_use]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Requests sent so far, per endpoint
+    #[must_use]
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    async fn scrape_from_api(
+        &self,
+        api_url: &str,
+    ) -> Result<Vec<FilmwebTitle>, FilmwebScrapeError> {
+        log::trace!(target: "film_events", "api_url: {:?}", api_url);
+
+        let mut found_titles: Vec<FilmwebTitle> = Vec::new();
+        let search_results = parse_search_results(&self.transport.get(api_url).await?.text())?;
+
+        for hit in search_results.search_hits {
+            if let Some((title_type_str, title_type)) = hit_kind(&hit.hit_type) {
+                let film_preview_res = self
+                    .transport
+                    .get(&preview_url(&self.base_url, title_type_str, hit.id))
+                    .await?
+                    .text();
+                let preview_result: Preview = serde_json::from_str(&film_preview_res)?;
+                let mut title = FilmwebTitle::from_preview(
+                    &self.base_url,
+                    hit.id,
+                    title_type_str,
+                    title_type,
+                    preview_result,
+                );
+                title.lazy = Some(LazyTransport::Async(Arc::clone(&self.transport)));
+                found_titles.push(title);
+            }
+        }
+        Ok(found_titles)
+    }
+
+    /// Scrapes Filmweb's database with a given query, see [`Filmweb::scrape`]
+    pub async fn scrape(
+        &self,
+        query: &Query,
+        page: u16,
+    ) -> Result<Vec<FilmwebTitle>, FilmwebScrapeError> {
+        let url = query.url_on(&self.base_url, page);
+        self.scrape_from_api(&url).await
+    }
+}
+
+fn parse_search_results(response: &str) -> Result<SearchResults, FilmwebScrapeError> {
+    Ok(serde_json::from_str(response)?)
+}
+
+/// Returns the url segment and the type of a search hit, or `None` if it isn't a film, a serial
+/// or a game
+const fn hit_kind(hit_type: &Type) -> Option<(&'static str, TitleType)> {
+    match hit_type {
+        Type::Film => Some(("film", TitleType::Movie)),
+        Type::Serial => Some(("film", TitleType::Show)),
+        Type::Game => Some(("game", TitleType::Game)),
+        _ => None,
+    }
+}
+
+fn preview_url(base_url: &str, title_type_str: &str, id: u32) -> String {
+    format!("{base_url}/api/v1/{title_type_str}/{id}/preview")
+}
+
+/// Transport fetching what a title was built without the first time it's needed
+#[derive(Debug, Clone)]
+pub(super) enum LazyTransport {
+    Blocking(Arc<dyn Transport>),
+    /// Used by the async accessors, e.g. [`Title::duration_async`]
+    #[cfg(feature = "async")]
+    Async(Arc<dyn AsyncTransport>),
+}
+
 /// Filmweb title struct with Title trait implemented, and other methods
 #[derive(Debug)]
 pub struct FilmwebTitle {
@@ -359,9 +491,173 @@ pub struct FilmwebTitle {
     genres: OnceCell<Vec<Genre>>,
     alter_titles: Option<PriorityQueue<AlternateTitle, u8>>,
     title_type: TitleType,
-    duration: Option<u16>, // in minutes
+    duration: OnceCell<Option<u16>>, // in minutes
     year: Year,
     imdb_data: Option<imdb::IMDbTitle>,
+    /// Fetches alternate titles and duration left out of a listing the first time they're
+    /// needed, browsing titles doesn't pay for two extra page loads per title
+    lazy: Option<LazyTransport>,
+    /// Titles a library listing came with, the only alternate ones until they're used up
+    listed_titles: Vec<String>,
+    /// `Some` for games only
+    game: Option<GameDetails>,
+}
+
+/// What Filmweb lists about a game on top of what it lists about films
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct GameDetails {
+    /// e.g. `PC` or `PlayStation 5`
+    pub platforms: Vec<String>,
+    pub developer: Option<String>,
+}
+
+impl FilmwebTitle {
+    /// Platforms and developer of a game, `None` for films and serials
+    #[must_use]
+    pub const fn game(&self) -> Option<&GameDetails> {
+        self.game.as_ref()
+    }
+
+    /// Builds a title out of Filmweb's preview api response, without alternate titles
+    fn from_preview(
+        base_url: &str,
+        id: u32,
+        title_type_str: &str,
+        title_type: TitleType,
+        preview: Preview,
+    ) -> Self {
+        let year = preview.year;
+        let name = preview
+            .title
+            .map(|title| title.title)
+            .or_else(|| Some(preview.original_title.unwrap().title))
+            .expect("it'll always be some");
+        // Games have genres of their own, which don't share ids with films' genres
+        let genres: Vec<FilmwebGenre> = match title_type {
+            TitleType::Game => Vec::new(),
+            TitleType::Movie | TitleType::Show => preview
+                .genres
+                .into_iter()
+                .map(|genre| FilmwebGenre::from_u8(genre.id).unwrap())
+                .collect(),
+        };
+        let game = (title_type == TitleType::Game).then(|| GameDetails {
+            platforms: preview.platforms.into_iter().map(|p| p.name).collect(),
+            developer: preview.developers.into_iter().next().map(|d| d.name),
+        });
+        let title_url = format!("{base_url}/{title_type_str}/{name}-{year}-{id}");
+        Self {
+            alter_titles: None,
+            name,
+            fw_genres: genres,
+            genres: OnceCell::new(),
+            id: TitleID::FilmwebID(id),
+            year: year.into(),
+            duration: OnceCell::with_value(preview.duration),
+            title_type,
+            imdb_data: None,
+            url: title_url,
+            lazy: None,
+            listed_titles: Vec::new(),
+            game,
+        }
+    }
+
+    fn blocking(&self) -> Option<&dyn Transport> {
+        match self.lazy.as_ref()? {
+            LazyTransport::Blocking(transport) => Some(&**transport),
+            #[cfg(feature = "async")]
+            LazyTransport::Async(_) => None,
+        }
+    }
+
+    #[cfg(feature = "async")]
+    fn nonblocking(&self) -> Option<Arc<dyn AsyncTransport>> {
+        match self.lazy.as_ref()? {
+            LazyTransport::Async(transport) => Some(Arc::clone(transport)),
+            LazyTransport::Blocking(_) => None,
+        }
+    }
+
+    /// Fetches the title page for the duration
+    fn fetch_duration(&self, transport: &dyn Transport) -> Result<Option<u16>, TransportError> {
+        let response = transport.get(&self.url)?.error_for_status(&self.url)?;
+        Ok(parse_duration(&response.text(), &self.url))
+    }
+
+    #[cfg(feature = "async")]
+    async fn fetch_duration_async(
+        &self,
+        transport: &dyn AsyncTransport,
+    ) -> Result<Option<u16>, TransportError> {
+        let response = transport.get(&self.url).await?;
+        Ok(parse_duration(
+            &response.error_for_status(&self.url)?.text(),
+            &self.url,
+        ))
+    }
+
+    fn fetch_alter_titles(&self) -> Option<PriorityQueue<AlternateTitle, u8>> {
+        let transport = self.blocking()?;
+        let url = format!("{}/titles", self.url);
+        AlternateTitle::fw_get_titles(&url, transport)
+            .map_err(|e| log::warn!("Couldn't fetch alternate titles of {}: {e}", self.url))
+            .ok()
+    }
+
+    /// Whether alternate titles are still to be fetched, or only the listed ones are known and
+    /// they've been used up
+    fn needs_alter_titles(&self) -> bool {
+        self.alter_titles
+            .as_ref()
+            .is_none_or(|titles| !self.listed_titles.is_empty() && titles.is_empty())
+    }
+
+    /// Keeps fetched alternate titles but the listed ones, which have been used up already
+    fn keep_alter_titles(&mut self, fetched: PriorityQueue<AlternateTitle, u8>) {
+        let listed = std::mem::take(&mut self.listed_titles);
+        self.alter_titles = Some(
+            fetched
+                .into_iter()
+                .filter(|(alternate, _)| !listed.contains(&alternate.title))
+                .collect(),
+        );
+    }
+
+    /// Builds a title out of a preview of a library listing. Its alternate titles are just the
+    /// main and the original one, as the `/titles` page of every title is what listing avoids.
+    /// The page is fetched with `lazy` once they're used up
+    fn from_listed_preview(
+        base_url: &str,
+        id: u32,
+        title_type_str: &str,
+        title_type: TitleType,
+        preview: Preview,
+        lazy: Option<LazyTransport>,
+    ) -> Self {
+        let mut alter_titles = PriorityQueue::new();
+        let listed = [
+            ("główny", preview.title.as_ref().map(|title| &title.title)),
+            (
+                "oryginalny",
+                preview.original_title.as_ref().map(|title| &title.title),
+            ),
+        ];
+        for (language, title) in listed {
+            if let Some(title) = title {
+                let alternate = AlternateTitle {
+                    language: language.to_owned(),
+                    title: title.clone(),
+                };
+                alter_titles.push(alternate, AlternateTitle::score_title(language));
+            }
+        }
+        let mut title = Self::from_preview(base_url, id, title_type_str, title_type, preview);
+        title.listed_titles = alter_titles.iter().map(|(t, _)| t.title.clone()).collect();
+        title.alter_titles = Some(alter_titles);
+        title.lazy = lazy;
+        title
+    }
 }
 
 impl Title for FilmwebTitle {
@@ -381,8 +677,37 @@ impl Title for FilmwebTitle {
         &self.title_type
     }
 
+    /// Fetched the first time it's needed if it wasn't listed. A failed fetch, e.g. answered
+    /// with an error page, is logged and retried on the next call. Titles of the async flavours
+    /// fetch it with [`Title::duration_async`] only
     fn duration(&self) -> Option<u16> {
+        let Some(transport) = self.blocking() else {
+            return self.duration.get().copied().flatten();
+        };
         self.duration
+            .get_or_try_init(|| self.fetch_duration(transport))
+            .map_err(|e| log::warn!("Couldn't fetch the duration of {}: {e}", self.url))
+            .ok()
+            .copied()
+            .flatten()
+    }
+
+    /// Fetched the first time it's needed, like [`FilmwebTitle::duration`]
+    #[cfg(feature = "async")]
+    async fn duration_async(&self) -> Option<u16> {
+        let Some(transport) = self.nonblocking() else {
+            return self.duration();
+        };
+        if let Some(duration) = self.duration.get() {
+            return *duration;
+        }
+        match self.fetch_duration_async(&*transport).await {
+            Ok(duration) => *self.duration.get_or_init(|| duration),
+            Err(e) => {
+                log::warn!("Couldn't fetch the duration of {}: {e}", self.url);
+                None
+            }
+        }
     }
 
     fn genres(&self) -> &Vec<Genre> {
@@ -406,7 +731,32 @@ impl Title for FilmwebTitle {
 }
 
 impl AlternateTitles for FilmwebTitle {
+    /// Fetched the first time they're needed if they weren't listed. Listed titles come with
+    /// their main and original title only, the others are fetched once those are popped. A
+    /// failed fetch is retried on the next call. Titles of the async flavours fetch them with
+    /// [`AlternateTitles::alter_titles_async`] only
     fn alter_titles(&mut self) -> Option<&mut PriorityQueue<AlternateTitle, u8>> {
+        if self.needs_alter_titles() {
+            if let Some(fetched) = self.fetch_alter_titles() {
+                self.keep_alter_titles(fetched);
+            }
+        }
+        self.alter_titles.as_mut()
+    }
+
+    /// Fetched the first time they're needed, like [`FilmwebTitle::alter_titles`]
+    #[cfg(feature = "async")]
+    async fn alter_titles_async(&mut self) -> Option<&mut PriorityQueue<AlternateTitle, u8>> {
+        let Some(transport) = self.nonblocking() else {
+            return self.alter_titles();
+        };
+        if self.needs_alter_titles() {
+            let url = format!("{}/titles", self.url);
+            match AlternateTitle::fw_get_titles_async(&url, &*transport).await {
+                Ok(fetched) => self.keep_alter_titles(fetched),
+                Err(e) => log::warn!("Couldn't fetch alternate titles of {}: {e}", self.url),
+            }
+        }
         self.alter_titles.as_mut()
     }
 }
@@ -446,18 +796,15 @@ impl AlternateTitle {
         }
     }
 
-    pub fn fw_get_titles(
-        url: &str,
-        client: &Client,
-    ) -> Result<PriorityQueue<Self, u8>, FilmwebErrors> {
-        let response = client.get(url).send().unwrap().text()?;
-        let document = Html::parse_document(&response);
+    /// Parses a Filmweb's `/titles` page into a queue of alternate titles, best scored first
+    #[must_use]
+    pub fn parse_titles_page(html: &str) -> PriorityQueue<Self, u8> {
+        let document = Html::parse_document(html);
         let select_titles = Selector::parse(".filmTitlesSection__title").unwrap();
         let select_language = Selector::parse(".filmTitlesSection__desc").unwrap();
         let mut titles = PriorityQueue::new();
         document
             .select(&select_titles)
-            .into_iter()
             .zip(document.select(&select_language))
             .for_each(|(title, language)| {
                 let title = title.inner_html();
@@ -465,7 +812,24 @@ impl AlternateTitle {
                 let score = Self::score_title(&language);
                 titles.push(Self { language, title }, score);
             });
-        Ok(titles)
+        titles
+    }
+
+    pub fn fw_get_titles(
+        url: &str,
+        transport: &dyn Transport,
+    ) -> Result<PriorityQueue<Self, u8>, FilmwebErrors> {
+        let response = transport.get(url)?.error_for_status(url)?;
+        Ok(Self::parse_titles_page(&response.text()))
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn fw_get_titles_async(
+        url: &str,
+        transport: &dyn AsyncTransport,
+    ) -> Result<PriorityQueue<Self, u8>, FilmwebErrors> {
+        let response = transport.get(url).await?.error_for_status(url)?;
+        Ok(Self::parse_titles_page(&response.text()))
     }
 }
 
@@ -473,22 +837,29 @@ impl AlternateTitle {
 mod tests {
     use crate::filmweb::auth::{FilmwebRatedTitle, FilmwebUser, UserPage};
     use crate::filmweb::query::QueryBuilder;
+    #[cfg(feature = "async")]
+    use crate::filmweb::AsyncFilmweb;
     use crate::filmweb::{Filmweb, FilmwebGenre};
-    use crate::{Title, TitleType, User, Year};
+    #[cfg(feature = "async")]
+    use crate::http::testing::async_cassette;
+    use crate::http::testing::cassette;
+    use crate::utils::mock_server::serve;
+    use crate::{AlternateTitle, AlternateTitles, Title, TitleType, User, Year};
     use std::env;
 
     struct Cookies {
         token: String,
         session: String,
         jwt: String,
-        username: String,
+        username: Option<String>,
     }
 
+    /// Cookies are only needed to record the cassette, they aren't replayed
     fn get_cookies() -> Cookies {
-        let token = env::var("FW_TOKEN").expect("cookies are set via env");
-        let session = env::var("FW_SESSION").unwrap();
-        let jwt = env::var("FW_JWT").unwrap();
-        let username = env::var("FW_USER").unwrap();
+        let token = env::var("FW_TOKEN").unwrap_or_default();
+        let session = env::var("FW_SESSION").unwrap_or_default();
+        let jwt = env::var("FW_JWT").unwrap_or_default();
+        let username = env::var("FW_USER").ok();
         Cookies {
             token,
             session,
@@ -499,7 +870,10 @@ mod tests {
 
     #[test]
     fn scraping_filmweb() {
-        let fw = Filmweb::new();
+        let fw = Filmweb::builder()
+            .transport(cassette("scraping_filmweb"))
+            .build()
+            .unwrap();
         let query = QueryBuilder::new()
             .year(Year::new(2021, 2021))
             .genres(vec![
@@ -516,16 +890,80 @@ mod tests {
         assert_eq!(year, 2021);
     }
 
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn scraping_filmweb_async() {
+        let fw: AsyncFilmweb = Filmweb::builder()
+            .async_transport(async_cassette("scraping_filmweb_async"))
+            .build_async()
+            .unwrap();
+        let query = QueryBuilder::new()
+            .year(Year::new(2021, 2021))
+            .genres(vec![
+                FilmwebGenre::Comedy,
+                FilmwebGenre::Drama,
+                FilmwebGenre::SciFi,
+            ])
+            .build();
+        let fw_search_result = fw.scrape(&query, 1).await.unwrap();
+
+        assert_eq!(fw_search_result[0].title(), "Diuna");
+    }
+
+    #[test]
+    fn scraping_filmweb_from_custom_base_url() {
+        let base_url = serve(vec![
+            (
+                "/api/v1/films/search",
+                r#"{"total":1,"searchHits":[{"id":1,"type":"film"}]}"#,
+            ),
+            (
+                "/api/v1/film/1/preview",
+                r#"{"year":2021,"title":{"title":"Diuna","country":"PL","lang":"pl"},"genres":[{"id":33}],"duration":155}"#,
+            ),
+            (
+                "/film/Diuna-2021-1",
+                r#"<div class="filmTitlesSection__title">Dune</div><div class="filmTitlesSection__desc">USA</div>"#,
+            ),
+        ]);
+        let fw = Filmweb::builder().base_url(&base_url).build().unwrap();
+        let mut results = fw.scrape(&QueryBuilder::new().build(), 1).unwrap();
+
+        assert_eq!(results[0].title(), "Diuna");
+        assert_eq!(results[0].duration(), Some(155));
+        assert!(results[0].url().starts_with(&base_url));
+        let alter_titles = results[0].alter_titles().unwrap();
+        assert_eq!(alter_titles.pop().unwrap().0.title, "Dune");
+    }
+
+    #[test]
+    fn parsing_alternate_titles() {
+        let html = r#"
+            <div class="filmTitlesSection__title">Diuna</div>
+            <div class="filmTitlesSection__desc">główny</div>
+            <div class="filmTitlesSection__title">Dune</div>
+            <div class="filmTitlesSection__desc">USA</div>"#;
+        let mut titles = AlternateTitle::parse_titles_page(html);
+        assert_eq!(titles.len(), 2);
+        assert_eq!(titles.pop().unwrap().0.title, "Dune");
+        assert_eq!(titles.pop().unwrap().0.title, "Diuna");
+    }
+
     #[test]
     fn creating_fwuser_and_username_checking_and_counts_querying() {
         let cookies = get_cookies();
-        let user = FilmwebUser::new(cookies.token, cookies.session, cookies.jwt).unwrap();
+        let user = FilmwebUser::builder()
+            .transport(cassette("creating_fwuser"))
+            .build(cookies.token, cookies.session, cookies.jwt)
+            .unwrap();
         let rated_films: Vec<FilmwebRatedTitle> =
             user.scrape(UserPage::RatedFilms(2)).unwrap().rated_titles;
 
         assert!(!rated_films.is_empty());
         assert!(!user.username().is_empty());
         assert!(user.num_of_rated_movies() > 0);
-        assert_eq!(cookies.username, *user.username());
+        if let Some(username) = cookies.username {
+            assert_eq!(username, *user.username());
+        }
     }
 }
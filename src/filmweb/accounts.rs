@@ -0,0 +1,222 @@
+use super::auth::{FilmwebCookies, RatedPage, UserPage};
+use super::{FilmwebErrors, FilmwebSession, FilmwebUser, FilmwebUserBuilder};
+
+use std::collections::BTreeMap;
+
+/// Outcome of an operation on every account, by account name. A failing account doesn't stop
+/// the others
+pub type AccountResults<T> = BTreeMap<String, Result<T, FilmwebErrors>>;
+
+/// Several Filmweb accounts exported in one run, e.g. every member of a household. Accounts are
+/// built from clones of one [`FilmwebUserBuilder`], so they share its rate limiter and metrics.
+/// Each account gets a transport of its own, unless the builder was given one
+///
+/// # Examples
+/// ```rust,no_run
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use filmed::filmweb::auth::UserPage;
+/// use filmed::filmweb::{FilmwebAccounts, FilmwebSession};
+///
+/// let mut accounts = FilmwebAccounts::new();
+/// accounts.add_session("alice", FilmwebSession::load("alice.json")?)?;
+/// accounts.add_session("bob", FilmwebSession::load("bob.json")?)?;
+///
+/// for (name, page) in accounts.scrape(UserPage::RatedFilms(1)) {
+///     match page {
+///         Ok(page) => println!("{name}: {} titles", page.rated_titles.len()),
+///         Err(e) => eprintln!("{name}: {e}"),
+///     }
+/// }
+/// for (name, session) in accounts.sessions() {
+///     session.save(format!("{name}.json"))?;
+/// }
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FilmwebAccounts {
+    builder: FilmwebUserBuilder,
+    accounts: BTreeMap<String, FilmwebUser>,
+}
+
+impl FilmwebAccounts {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_builder(FilmwebUserBuilder::new())
+    }
+
+    /// Builds every account with clones of `builder`
+    #[must_use]
+    pub fn with_builder(builder: FilmwebUserBuilder) -> Self {
+        Self {
+            builder,
+            accounts: BTreeMap::new(),
+        }
+    }
+
+    /// Brings back a saved session as account `name`, replacing an account of the same name
+    pub fn add_session<T: Into<String>>(
+        &mut self,
+        name: T,
+        session: FilmwebSession,
+    ) -> Result<&FilmwebUser, FilmwebErrors> {
+        let user = self.builder.clone().restore(session)?;
+        Ok(self.insert(name.into(), user))
+    }
+
+    /// Logs in with `cookies` as account `name`, replacing an account of the same name
+    pub fn add_cookies<T: Into<String>>(
+        &mut self,
+        name: T,
+        cookies: FilmwebCookies,
+    ) -> Result<&FilmwebUser, FilmwebErrors> {
+        let user = self.builder.clone().build_with_cookies(cookies)?;
+        Ok(self.insert(name.into(), user))
+    }
+
+    /// Brings back every saved session, keeping the ones that are still valid
+    pub fn add_sessions<I, T>(&mut self, sessions: I) -> AccountResults<()>
+    where
+        I: IntoIterator<Item = (T, FilmwebSession)>,
+        T: Into<String>,
+    {
+        sessions
+            .into_iter()
+            .map(|(name, session)| {
+                let name = name.into();
+                let added = self.add_session(name.clone(), session).map(|_| ());
+                if let Err(e) = &added {
+                    log::warn!("Couldn't restore the session of {name}: {e}");
+                }
+                (name, added)
+            })
+            .collect()
+    }
+
+    fn insert(&mut self, name: String, user: FilmwebUser) -> &FilmwebUser {
+        self.accounts.insert(name.clone(), user);
+        &self.accounts[&name]
+    }
+
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&FilmwebUser> {
+        self.accounts.get(name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<FilmwebUser> {
+        self.accounts.remove(name)
+    }
+
+    /// Account names, in alphabetical order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.accounts.keys().map(String::as_str)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Calls `f` with every account in turn. An error, e.g. a JWT that couldn't be renewed, is
+    /// logged and kept among the results while the remaining accounts carry on
+    pub fn run<T, F>(&self, mut f: F) -> AccountResults<T>
+    where
+        F: FnMut(&str, &FilmwebUser) -> Result<T, FilmwebErrors>,
+    {
+        self.accounts
+            .iter()
+            .map(|(name, user)| {
+                let result = f(name, user);
+                if let Err(e) = &result {
+                    log::warn!("Account {name} failed: {e}");
+                }
+                (name.clone(), result)
+            })
+            .collect()
+    }
+
+    /// Scrapes the same user page of every account
+    pub fn scrape(&self, page: UserPage) -> AccountResults<RatedPage> {
+        self.run(|_, user| user.scrape(page))
+    }
+
+    /// Current session of every account, with renewed JWTs, to be saved after the run
+    #[must_use]
+    pub fn sessions(&self) -> BTreeMap<String, FilmwebSession> {
+        self.accounts
+            .iter()
+            .map(|(name, user)| (name.clone(), user.session()))
+            .collect()
+    }
+}
+
+impl Default for FilmwebAccounts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filmweb::auth::FilmwebUserCounts;
+    use crate::http::testing::FakeTransport;
+    use crate::http::{Endpoint, Metrics};
+    use crate::User;
+
+    use std::sync::Arc;
+
+    fn session(username: &str) -> FilmwebSession {
+        FilmwebSession::new(
+            FilmwebCookies::new("token", "session", "jwt"),
+            username.to_owned(),
+            FilmwebUserCounts::default(),
+        )
+    }
+
+    #[test]
+    fn running_every_account() {
+        let settings = r#"<div class="mainSettings__groupItemStateContent">a</div>
+            <div class="mainSettings__groupItemStateContent">b</div>
+            <div class="mainSettings__groupItemStateContent">tester</div>"#;
+        let transport = Arc::new(FakeTransport::new(vec![("/settings", settings)]));
+        let metrics = Metrics::new();
+        let mut accounts = FilmwebAccounts::with_builder(
+            FilmwebUserBuilder::new()
+                .base_url("http://filmweb.test")
+                .transport(Arc::clone(&transport))
+                .metrics(metrics.clone()),
+        );
+
+        let added = accounts.add_sessions([
+            ("alice", session("tester")),
+            ("bob", session("tester")),
+            ("carol", session("someone else")),
+        ]);
+        assert!(added["alice"].is_ok() && added["bob"].is_ok());
+        assert!(matches!(
+            added["carol"],
+            Err(FilmwebErrors::InvalidCredentials)
+        ));
+        assert_eq!(accounts.names().collect::<Vec<_>>(), vec!["alice", "bob"]);
+
+        let results = accounts.run(|name, user| {
+            if name == "alice" {
+                Err(FilmwebErrors::InvalidJwt)
+            } else {
+                Ok(user.username().clone())
+            }
+        });
+        assert!(matches!(results["alice"], Err(FilmwebErrors::InvalidJwt)));
+        assert_eq!(results["bob"].as_ref().unwrap(), "tester");
+        assert_eq!(accounts.sessions()["bob"].username, "tester");
+        assert_eq!(metrics.snapshot().get(Endpoint::Other).requests, 3);
+    }
+}
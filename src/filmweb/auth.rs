@@ -1,25 +1,42 @@
 /// Module containing logged-in user related things.
-use crate::{
-    imdb::IMDb, utils::ClientPool, AlternateTitles, IMDbLookup, RatedTitle, TitleID, User,
-};
+#[cfg(feature = "async")]
+use crate::http::AsyncTransport;
+use crate::http::{HttpRequest, HttpResponse, Metrics, MetricsSnapshot, Transport, TransportError};
+use crate::{imdb::IMDb, AlternateTitles, IMDbLookup, RatedTitle, TitleID, User};
 
+use super::credentials::{CredentialStore, StoredCredentials};
+use super::json::FilmwebApiComment;
+#[cfg(feature = "async")]
+use super::library::AsyncUserApi;
+use super::library::{ListOwner, RatedTitles, UserApi, WatchlistOrder};
 use super::{
-    imdb, parse_my_votebox, AlternateTitle, Deref, FilmwebErrors, FilmwebTitle,
-    ScrapedFilmwebTitleData, Title, TitleType, Year, USER_AGENT,
+    builder::FilmwebUserBuilder, imdb, library, login, AlternateTitle, FilmwebErrors,
+    FilmwebSession, FilmwebTitle, GameDetails, JwtClaims, LazyTransport, ScrapedFilmwebTitleData,
+    Title, TitleType, Year,
 };
 use csv::Writer;
 use once_cell::sync::OnceCell;
-use reqwest::blocking::Client;
-use reqwest::header;
 use scraper::{Html, Selector};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use time::{Date, Month, OffsetDateTime};
 
+/// Logged-in Filmweb user. Its cookies are added to every request, so a renewed JWT is used by
+/// every member of a [`ClientPool`](crate::http::ClientPool) right away
 #[derive(Debug)]
 pub struct FilmwebUser {
-    fw_client_pool: ClientPool,
+    transport: Arc<dyn Transport>,
+    cookies: RwLock<FilmwebCookies>,
+    base_url: String,
+    metrics: Metrics,
     username: String,
     counts: FilmwebUserCounts,
+    credentials: Option<StoredCredentials>,
 }
 
 #[derive(Debug)]
@@ -32,6 +49,21 @@ pub struct ExportFiles {
     pub generic: Writer<File>,
     pub want2see: Writer<File>,
     pub favorited: Writer<File>,
+    pub letterboxd: Writer<File>,
+}
+
+/// Starts a Letterboxd import CSV, see [`FilmwebRatedTitle::to_csv_letterboxd`]
+pub fn letterboxd_writer<W: Write>(inner: W) -> Result<Writer<W>, csv::Error> {
+    let mut wtr = csv::Writer::from_writer(inner);
+    wtr.write_record([
+        "imdbID",
+        "Title",
+        "Year",
+        "Rating10",
+        "WatchedDate",
+        "Review",
+    ])?;
+    Ok(wtr)
 }
 
 impl ExportFiles {
@@ -65,13 +97,16 @@ impl ExportFiles {
         let generic = File::create("exports/generic.csv")?;
         let want2see = File::create("exports/want2see.csv")?;
         let favorited = File::create("exports/favorited.csv")?;
+        let letterboxd = File::create("exports/letterboxd.csv")?;
         let generic = write_header(generic);
         let want2see = write_header(want2see);
         let favorited = write_header(favorited);
+        let letterboxd = letterboxd_writer(letterboxd)?;
         Ok(Self {
             generic,
             want2see,
             favorited,
+            letterboxd,
         })
     }
 }
@@ -82,13 +117,29 @@ impl Default for ExportFiles {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct FilmwebUserCounts {
     pub movies: u16,
     pub shows: u16,
+    /// Missing from sessions saved before games were counted
+    #[serde(default)]
+    pub games: u16,
     pub watchlist: u16,
 }
 
+impl FilmwebUserCounts {
+    /// Number of titles on all pages of `page_type`
+    #[must_use]
+    pub const fn of(&self, page_type: UserPageType) -> u16 {
+        match page_type {
+            UserPageType::RatedFilms => self.movies,
+            UserPageType::RatedShows => self.shows,
+            UserPageType::RatedGames => self.games,
+            UserPageType::Watchlist => self.watchlist,
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct FilmwebApiDetails {
     pub rate: u8,
@@ -96,45 +147,109 @@ pub struct FilmwebApiDetails {
     #[serde(rename = "viewDate")]
     pub view_date: u32,
     pub timestamp: u128,
+    #[serde(flatten)]
+    pub comment: FilmwebApiComment,
+}
+
+/// Short review a user wrote along with their rating
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RatingComment {
+    pub text: String,
+    /// When it was written, or last edited
+    pub written_at: Option<OffsetDateTime>,
+    pub likes: u32,
+}
+
+impl FilmwebApiComment {
+    /// The comment, `None` when it's missing or blank
+    fn into_comment(self) -> Option<RatingComment> {
+        let text = self.comment?.trim().to_owned();
+        (!text.is_empty()).then(|| RatingComment {
+            text,
+            written_at: parse_rating_time(self.timestamp.into()),
+            likes: self.likes,
+        })
+    }
+}
+
+/// How much the user wants to see a title on their watchlist, and when it was added there
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FilmwebWantToSeeDetails {
+    #[serde(default)]
+    pub level: u8,
+    #[serde(default)]
+    pub timestamp: u128,
 }
 
 /// Enum that defines a url of rated titles or watchlisted titles.  
 /// RatedFilms(2) would look like filmweb.pl/user/{USERNAME}/films?page=2  
 /// RatedShows(4) filmweb.pl/user/{USERNAME}/serials?page=4  
+/// RatedGames(3) filmweb.pl/user/{USERNAME}/games?page=3  
 /// Watchlist(6) filmweb.pl/user/{USERNAME}/wantToSee?page=6  
 #[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum UserPage {
-    RatedFilms(u8),
-    RatedShows(u8),
-    Watchlist(u8),
+    RatedFilms(u16),
+    RatedShows(u16),
+    RatedGames(u16),
+    Watchlist(u16),
 }
 
 /// Enum that defines type of a user page.  
 /// `RatedFilms` would look like filmweb.pl/user/{USERNAME}/films  
 /// `RatedShows` filmweb.pl/user/{USERNAME}/serials  
+/// `RatedGames` filmweb.pl/user/{USERNAME}/games  
 /// `Watchlist` filmweb.pl/user/{USERNAME}/wantToSee  
 #[derive(Copy, Clone, Deserialize, Serialize, Debug, PartialEq, Eq, Hash)]
 pub enum UserPageType {
     RatedFilms,
     RatedShows,
+    RatedGames,
     Watchlist,
 }
 
 impl UserPage {
-    ///
-    fn user_url(self, username: &str) -> String {
+    #[must_use]
+    pub const fn number(self) -> u16 {
+        match self {
+            Self::RatedFilms(p)
+            | Self::RatedShows(p)
+            | Self::RatedGames(p)
+            | Self::Watchlist(p) => p,
+        }
+    }
+
+    /// Page that follows this one
+    #[must_use]
+    pub const fn next(self) -> Self {
+        match self {
+            Self::RatedFilms(p) => Self::RatedFilms(p + 1),
+            Self::RatedShows(p) => Self::RatedShows(p + 1),
+            Self::RatedGames(p) => Self::RatedGames(p + 1),
+            Self::Watchlist(p) => Self::Watchlist(p + 1),
+        }
+    }
+
+    pub(super) fn user_url(self, base_url: &str, username: &str) -> String {
         match self {
             Self::RatedFilms(p) => {
-                format!("https://www.filmweb.pl/user/{username}/films?page={p}")
+                format!("{base_url}/user/{username}/films?page={p}")
             }
-            Self::RatedShows(p) => format!(
-                "https://www.filmweb.pl/user/{}/serials?page={}",
-                username, p
-            ),
-            Self::Watchlist(p) => format!(
-                "https://www.filmweb.pl/user/{}/wantToSee?page={}",
-                username, p
-            ),
+            Self::RatedShows(p) => format!("{}/user/{}/serials?page={}", base_url, username, p),
+            Self::RatedGames(p) => format!("{base_url}/user/{username}/games?page={p}"),
+            Self::Watchlist(p) => format!("{}/user/{}/wantToSee?page={}", base_url, username, p),
+        }
+    }
+}
+
+impl UserPageType {
+    /// Page number `page` of this type
+    #[must_use]
+    pub const fn page(self, page: u16) -> UserPage {
+        match self {
+            Self::RatedFilms => UserPage::RatedFilms(page),
+            Self::RatedShows => UserPage::RatedShows(page),
+            Self::RatedGames => UserPage::RatedGames(page),
+            Self::Watchlist => UserPage::Watchlist(page),
         }
     }
 }
@@ -144,6 +259,7 @@ impl From<UserPage> for UserPageType {
         match fw_page_number {
             UserPage::RatedFilms(_) => Self::RatedFilms,
             UserPage::RatedShows(_) => Self::RatedShows,
+            UserPage::RatedGames(_) => Self::RatedGames,
             UserPage::Watchlist(_) => Self::Watchlist,
         }
     }
@@ -153,8 +269,13 @@ impl From<UserPage> for UserPageType {
 pub struct FilmwebRatedTitle {
     title: FilmwebTitle,
     rating: Option<u8>,
-    is_favorited: bool,
+    is_favorited: Option<bool>,
     is_watchlisted: bool,
+    rated_at: Option<OffsetDateTime>,
+    view_date: Option<Date>,
+    interest: Option<u8>,
+    watchlisted_at: Option<OffsetDateTime>,
+    comment: Option<RatingComment>,
 }
 
 impl RatedTitle for FilmwebRatedTitle {
@@ -162,13 +283,22 @@ impl RatedTitle for FilmwebRatedTitle {
         self.rating
     }
 
+    /// `false` also when it's unknown, see [`FilmwebRatedTitle::favorited`]
     fn is_favorited(&self) -> bool {
-        self.is_favorited
+        self.is_favorited == Some(true)
     }
 
     fn is_watchlisted(&self) -> bool {
         self.is_watchlisted
     }
+
+    fn rated_at(&self) -> Option<OffsetDateTime> {
+        self.rated_at
+    }
+
+    fn view_date(&self) -> Option<Date> {
+        self.view_date
+    }
 }
 
 impl FilmwebRatedTitle {
@@ -189,6 +319,16 @@ impl FilmwebRatedTitle {
             Year::OneYear(year) | Year::Range(year, _) => year.to_string(),
         };
 
+        let date_rated = self.rated_at.map(|rated_at| {
+            let date = rated_at.date();
+            format!(
+                "{}-{:02}-{:02}",
+                date.year(),
+                u8::from(date.month()),
+                date.day()
+            )
+        });
+
         log::debug!(
             "Exporting to CSV title: {}, rating: {}, imdb_id: {}",
             title,
@@ -198,6 +338,7 @@ impl FilmwebRatedTitle {
         let mut fields = [""; 13];
         fields[0] = imdb_id;
         fields[1] = rating.as_ref();
+        fields[2] = date_rated.as_deref().unwrap_or_default();
         fields[3] = title.as_ref();
         fields[9] = year.as_ref();
         let write_title = |file: &mut Writer<File>| {
@@ -211,22 +352,85 @@ impl FilmwebRatedTitle {
             _ => panic!("It can't be possible"),
         }
     }
+
+    /// Writes the title as a row of a Letterboxd import, with the user's comment as the review.
+    /// Watchlisted titles without a rating are skipped
+    pub fn to_csv_letterboxd<W: Write>(&self, wtr: &mut Writer<W>) -> Result<(), csv::Error> {
+        let Some(rating) = self.rating else {
+            return Ok(());
+        };
+        let imdb_id = match self.title.imdb_data().map(imdb::IMDbTitle::id) {
+            Some(TitleID::IMDbID(id)) => id.as_str(),
+            _ => "",
+        };
+        let year = match self.title.year {
+            Year::OneYear(year) | Year::Range(year, _) => year.to_string(),
+        };
+        let watched_date = self.view_date.map(|date| {
+            format!(
+                "{}-{:02}-{:02}",
+                date.year(),
+                u8::from(date.month()),
+                date.day()
+            )
+        });
+        wtr.write_record([
+            imdb_id,
+            self.title.title(),
+            &year,
+            &rating.to_string(),
+            watched_date.as_deref().unwrap_or_default(),
+            self.comment.as_ref().map_or("", |c| c.text.as_str()),
+        ])
+    }
 }
 
 impl FilmwebRatedTitle {
-    const fn new(
-        title: FilmwebTitle,
-        rating: Option<u8>,
-        favorited: bool,
-        watchlisted: bool,
-    ) -> Self {
+    pub(super) fn new(title: FilmwebTitle, vote: Vote) -> Self {
         Self {
             title,
-            rating,
-            is_favorited: favorited,
-            is_watchlisted: watchlisted,
+            rating: vote.rating,
+            is_favorited: vote.favorited,
+            is_watchlisted: vote.watchlisted,
+            rated_at: vote.rated_at,
+            view_date: vote.view_date,
+            interest: vote.interest,
+            watchlisted_at: vote.watchlisted_at,
+            comment: vote.comment,
         }
     }
+
+    /// Whether the title is among user's favorites, `None` when it can't be told, e.g. on a
+    /// [`PublicFilmwebUser`](super::PublicFilmwebUser) profile
+    #[must_use]
+    pub const fn favorited(&self) -> Option<bool> {
+        self.is_favorited
+    }
+
+    /// See [`FilmwebTitle::game`]
+    #[must_use]
+    pub const fn game(&self) -> Option<&GameDetails> {
+        self.title.game()
+    }
+
+    /// How much the user wants to see a title on their watchlist, higher is more. `None` for
+    /// rated titles, or when Filmweb didn't tell
+    #[must_use]
+    pub const fn interest_level(&self) -> Option<u8> {
+        self.interest
+    }
+
+    /// When the title was added to the watchlist, `None` for rated titles
+    #[must_use]
+    pub const fn watchlisted_at(&self) -> Option<OffsetDateTime> {
+        self.watchlisted_at
+    }
+
+    /// Short review the user wrote along with their rating, if they wrote one
+    #[must_use]
+    pub const fn comment(&self) -> Option<&RatingComment> {
+        self.comment.as_ref()
+    }
 }
 
 impl AsRef<FilmwebTitle> for FilmwebRatedTitle {
@@ -256,6 +460,11 @@ impl Title for FilmwebRatedTitle {
         self.title.duration()
     }
 
+    #[cfg(feature = "async")]
+    async fn duration_async(&self) -> Option<u16> {
+        self.title.duration_async().await
+    }
+
     fn year(&self) -> Year {
         self.title.year()
     }
@@ -269,6 +478,13 @@ impl AlternateTitles for FilmwebRatedTitle {
     fn alter_titles(&mut self) -> Option<&mut priority_queue::PriorityQueue<AlternateTitle, u8>> {
         self.title.alter_titles()
     }
+
+    #[cfg(feature = "async")]
+    async fn alter_titles_async(
+        &mut self,
+    ) -> Option<&mut priority_queue::PriorityQueue<AlternateTitle, u8>> {
+        self.title.alter_titles_async().await
+    }
 }
 
 impl IMDbLookup for FilmwebRatedTitle {
@@ -285,211 +501,528 @@ impl IMDbLookup for FilmwebRatedTitle {
     }
 }
 
-/// Reqwest client but with JWT,
-#[derive(Debug, Clone)]
-struct FilmwebUserHttpClient(Client);
+/// Cookies authenticating a Filmweb user, sent along every request of a [`FilmwebUser`]
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
+pub struct FilmwebCookies {
+    /// `_fwuser_token`
+    pub token: String,
+    /// `_fwuser_sessionId`
+    pub session: String,
+    /// `JWT`
+    pub jwt: String,
+}
 
-impl Deref for FilmwebUserHttpClient {
-    type Target = Client;
-    fn deref(&self) -> &Self::Target {
-        &self.0
+impl FilmwebCookies {
+    pub fn new<T: ToString>(token: T, session: T, jwt: T) -> Self {
+        Self {
+            token: token.to_string().trim().to_owned(),
+            session: session.to_string().trim().to_owned(),
+            jwt: jwt.to_string().trim().to_owned(),
+        }
     }
-}
 
-impl FilmwebUserHttpClient {
-    pub fn new(token: &str, session: &str, jwt: &str) -> Self {
-        let cookies = format!(
+    /// Decodes the claims of the JWT, see [`JwtClaims`]
+    pub fn claims(&self) -> Result<JwtClaims, FilmwebErrors> {
+        JwtClaims::decode(&self.jwt)
+    }
+
+    fn header_value(&self) -> String {
+        format!(
             "_fwuser_token={}; _fwuser_sessionId={}; JWT={};",
-            token.trim(),
-            session.trim(),
-            jwt.trim()
-        );
+            self.token, self.session, self.jwt
+        )
+    }
 
-        let mut headers = header::HeaderMap::new();
-        headers.insert(
-            header::COOKIE,
-            header::HeaderValue::from_str(&cookies).unwrap(),
-        );
-        headers.insert(
-            header::CONNECTION,
-            header::HeaderValue::from_static("keep-alive"),
-        );
-        headers.insert(
-            header::ACCEPT_ENCODING,
-            header::HeaderValue::from_static("gzip"),
+    fn authenticate(&self, request: HttpRequest) -> HttpRequest {
+        request.header("Cookie", self.header_value())
+    }
+}
+
+// Cookie values are secrets, keep them out of logs
+impl std::fmt::Debug for FilmwebCookies {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FilmwebCookies")
+            .field("token", &"<redacted>")
+            .field("session", &"<redacted>")
+            .field("jwt", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Replaces `cookies` with the ones rotated by `response`, so later requests aren't sent with
+/// stale ones
+fn keep_rotated(
+    cookies: &RwLock<FilmwebCookies>,
+    credentials: Option<&StoredCredentials>,
+    response: &HttpResponse,
+) {
+    let rotated = login::rotated(&cookies.read().unwrap(), response);
+    if let Some(rotated) = rotated {
+        log::debug!("Took cookies rotated by Filmweb");
+        replace_cookies(cookies, credentials, rotated);
+    }
+}
+
+/// Replaces `cookies` with `updated`, writing them back to `credentials` if they changed, so
+/// the store never holds cookies the server has already replaced
+fn replace_cookies(
+    cookies: &RwLock<FilmwebCookies>,
+    credentials: Option<&StoredCredentials>,
+    updated: FilmwebCookies,
+) {
+    let mut cookies = cookies.write().unwrap();
+    if *cookies == updated {
+        return;
+    }
+    if let Some(credentials) = credentials {
+        credentials.save(&updated);
+    }
+    *cookies = updated;
+}
+
+/// Url segment of the kind of a title scraped from a watchlist, which mixes films and serials
+pub(super) fn watchlisted_kind(scraped: &ScrapedFilmwebTitleData) -> &'static str {
+    if scraped.url.contains("/serial/") {
+        "serial"
+    } else {
+        "film"
+    }
+}
+
+pub(super) fn vote_details_url(
+    base_url: &str,
+    page: UserPage,
+    scraped: &ScrapedFilmwebTitleData,
+) -> String {
+    let id = scraped.id;
+    match page {
+        UserPage::RatedFilms(_) => format!("{base_url}/api/v1/logged/vote/film/{id}/details"),
+        UserPage::RatedShows(_) => format!("{base_url}/api/v1/logged/vote/serial/{id}/details"),
+        UserPage::RatedGames(_) => format!("{base_url}/api/v1/logged/vote/game/{id}/details"),
+        UserPage::Watchlist(_) => {
+            let kind = watchlisted_kind(scraped);
+            format!("{base_url}/api/v1/logged/want2see/{kind}/{id}/details")
+        }
+    }
+}
+
+/// What a user told about a title on their user page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Vote {
+    pub rating: Option<u8>,
+    /// `None` when it isn't known
+    pub favorited: Option<bool>,
+    pub watchlisted: bool,
+    pub rated_at: Option<OffsetDateTime>,
+    pub view_date: Option<Date>,
+    /// How much a title on a watchlist is wanted
+    pub interest: Option<u8>,
+    pub watchlisted_at: Option<OffsetDateTime>,
+    pub comment: Option<RatingComment>,
+}
+
+impl Vote {
+    /// Vote of a title on a watchlist, `favorited` as far as it's known. Filmweb sends zeroes
+    /// for unknown levels and dates
+    pub fn watchlisted(favorited: Option<bool>, level: u8, timestamp: u128) -> Self {
+        Self {
+            rating: None,
+            favorited,
+            watchlisted: true,
+            rated_at: None,
+            view_date: None,
+            interest: (level > 0).then_some(level),
+            watchlisted_at: parse_rating_time(timestamp),
+            comment: None,
+        }
+    }
+
+    /// Vote of a rated title. Filmweb sends zeroes for unknown dates
+    pub fn rated(rating: u8, favorited: Option<bool>, timestamp: u128, view_date: u32) -> Self {
+        Self {
+            rating: Some(rating),
+            favorited,
+            watchlisted: false,
+            rated_at: parse_rating_time(timestamp),
+            view_date: parse_view_date(view_date),
+            interest: None,
+            watchlisted_at: None,
+            comment: None,
+        }
+    }
+
+    /// Attaches the comment written along with the rating, if there's one
+    pub fn with_comment(mut self, comment: FilmwebApiComment) -> Self {
+        self.comment = comment.into_comment();
+        self
+    }
+}
+
+/// Reads milliseconds since the epoch
+fn parse_rating_time(timestamp: u128) -> Option<OffsetDateTime> {
+    if timestamp == 0 {
+        return None;
+    }
+    let nanos = i128::try_from(timestamp).ok()?.checked_mul(1_000_000)?;
+    OffsetDateTime::from_unix_timestamp_nanos(nanos).ok()
+}
+
+/// Reads a date written as `YYYYMMDD`
+fn parse_view_date(view_date: u32) -> Option<Date> {
+    let year = i32::try_from(view_date / 10_000).ok()?;
+    let month = Month::try_from(u8::try_from(view_date / 100 % 100).ok()?).ok()?;
+    let day = u8::try_from(view_date % 100).ok()?;
+    Date::from_calendar_date(year, month, day).ok()
+}
+
+/// Returns the vote from a vote details response, or from want-to-see details in case of a
+/// watchlist
+pub(super) fn parse_vote_details(page: UserPage, response: &str) -> Result<Vote, FilmwebErrors> {
+    let vote = match page {
+        UserPage::Watchlist(_) => serde_json::from_str::<FilmwebWantToSeeDetails>(response)
+            .map(|s| Vote::watchlisted(Some(false), s.level, s.timestamp)),
+        UserPage::RatedFilms(_) | UserPage::RatedShows(_) | UserPage::RatedGames(_) => {
+            serde_json::from_str::<FilmwebApiDetails>(response).map(|s| {
+                Vote::rated(
+                    s.rate,
+                    Some(s.favorite.unwrap_or(false)),
+                    s.timestamp,
+                    s.view_date,
+                )
+                .with_comment(s.comment)
+            })
+        }
+    };
+    vote.map_err(|e| {
+        log::info!("Bad Filmweb's api response: {response}\n{e}");
+        FilmwebErrors::InvalidJwt
+    })
+}
+
+fn warn_if_expires_within(claims: &JwtClaims, duration: Duration) -> bool {
+    let expires = claims.expires_within(duration);
+    if expires {
+        let remaining = claims.remaining().unwrap_or_default();
+        log::warn!(
+            "JWT expires in {}s, before the next {}s pass; it will have to be renewed",
+            remaining.as_secs(),
+            duration.as_secs()
         );
+    }
+    expires
+}
+
+/// An expired JWT is answered with an error page instead of the expected `T`
+fn is_jwt_expired<T: DeserializeOwned>(response: &HttpResponse) -> bool {
+    matches!(response.status, 401 | 403) || serde_json::from_slice::<T>(&response.body).is_err()
+}
+
+pub(super) fn rated_count_url(
+    base_url: &str,
+    username: &str,
+    title_type: &str,
+    title_type2: &str,
+) -> String {
+    format!(
+        "{}/api/v1/user/{}/{}/{}/count",
+        base_url, username, title_type, title_type2
+    )
+}
 
-        Self(
-            Client::builder()
-                .user_agent(USER_AGENT)
-                .gzip(true)
-                .default_headers(headers)
-                .cookie_store(true)
-                .build()
-                .unwrap(),
+fn parse_username(response: &str) -> Result<String, FilmwebErrors> {
+    let document = Html::parse_document(response);
+    document
+        .select(&Selector::parse(".mainSettings__groupItemStateContent").unwrap())
+        .nth(2)
+        .map_or_else(
+            || Err(FilmwebErrors::InvalidCredentials),
+            |username_tag| Ok(username_tag.inner_html().trim().to_owned()),
         )
-    }
+}
+
+impl FilmwebRatedTitle {
+    /// Puts together a title scraped from a user page with its details and vote
+    pub(super) fn from_scraped(
+        page: UserPage,
+        scraped: ScrapedFilmwebTitleData,
+        lazy: LazyTransport,
+        vote: Vote,
+    ) -> Self {
+        let ScrapedFilmwebTitleData {
+            id,
+            year,
+            genres: fw_genres,
+            name,
+            url,
+            game,
+        } = scraped;
 
-    pub fn into_client(self) -> Client {
-        self.0
+        let title_type = match page {
+            UserPage::RatedFilms(_) => TitleType::Movie,
+            UserPage::RatedShows(_) => TitleType::Show,
+            UserPage::RatedGames(_) => TitleType::Game,
+            UserPage::Watchlist(_) => {
+                if url.contains("/serial/") {
+                    TitleType::Show
+                } else {
+                    TitleType::Movie
+                }
+            }
+        };
+
+        // Games have no duration to fetch
+        let duration = if title_type == TitleType::Game {
+            OnceCell::with_value(None)
+        } else {
+            OnceCell::new()
+        };
+
+        let unrated_title = FilmwebTitle {
+            id: TitleID::FilmwebID(id),
+            url,
+            title_type,
+            fw_genres,
+            genres: OnceCell::new(),
+            name,
+            year,
+            alter_titles: None,
+            duration,
+            imdb_data: None,
+            lazy: Some(lazy),
+            listed_titles: Vec::new(),
+            game,
+        };
+
+        Self::new(unrated_title, vote)
     }
 }
 
 impl FilmwebUser {
     pub fn new<T: ToString>(token: T, session: T, jwt: T) -> Result<Self, FilmwebErrors> {
-        let token = token.to_string();
-        let session = session.to_string();
-        let jwt = jwt.to_string();
-        let fw_client = FilmwebUserHttpClient::new(&token, &session, &jwt);
-        let username = Self::get_username(&fw_client).unwrap();
-        let counts = Self::rated_counts(&username, &fw_client).unwrap();
-        let fw_client_pool = ClientPool::new(fw_client.into_client(), 5);
-        let user = Self {
-            fw_client_pool,
-            username,
-            counts,
+        Self::builder().build(token, session, jwt)
+    }
+
+    /// Logs in with cookies exported from a browser, as a Netscape `cookies.txt` or as JSON, see
+    /// [`FilmwebCookies::load`]
+    pub fn from_cookies_file<P: AsRef<Path>>(path: P) -> Result<Self, FilmwebErrors> {
+        Self::builder().build_with_cookies(FilmwebCookies::load(path)?)
+    }
+
+    /// Logs in with username and password instead of cookies copied from a browser
+    pub fn login(username: &str, password: &str) -> Result<Self, FilmwebErrors> {
+        Self::builder().login(username, password)
+    }
+
+    /// Logs in with cookies of `account` kept in `store`, see
+    /// [`FilmwebUserBuilder::build_from_store`]
+    pub fn from_store<S: CredentialStore + 'static>(
+        store: S,
+        account: &str,
+    ) -> Result<Self, FilmwebErrors> {
+        Self::builder().build_from_store(store, account)
+    }
+
+    /// Returns a builder to configure a user, e.g. to query a mock server or a proxy
+    #[must_use]
+    pub fn builder() -> FilmwebUserBuilder {
+        FilmwebUserBuilder::new()
+    }
+
+    pub(super) fn with_transport(
+        transport: Arc<dyn Transport>,
+        metrics: Metrics,
+        cookies: FilmwebCookies,
+        base_url: String,
+    ) -> Result<Self, FilmwebErrors> {
+        let mut user = Self {
+            transport,
+            metrics,
+            cookies: RwLock::new(cookies),
+            base_url,
+            username: String::new(),
+            counts: FilmwebUserCounts::default(),
+            credentials: None,
         };
+        user.username = user.get_username()?;
+        user.counts = library::rated_counts(&user)?;
         Ok(user)
     }
 
-    pub fn scrape(&self, page: UserPage) -> Result<RatedPage, FilmwebErrors> {
-        let mut rated_titles: Vec<_> = Vec::new();
-        let url = page.user_url(&self.username);
-        let res = self.fw_client_pool.get(url).send()?.text()?;
-
-        // Ensure that these elements do exist or else it will be critical
-        debug_assert!(res.contains("preview__link"));
-
-        let document = Html::parse_document(&res);
-        for votebox in document.select(&Selector::parse("div.myVoteBox").unwrap()) {
-            let ScrapedFilmwebTitleData {
-                id,
-                year,
-                genres: fw_genres,
-                name,
-                url,
-                alter_titles,
-                duration,
-            } = parse_my_votebox(votebox, &self.fw_client_pool)?;
-
-            let title_type = match page {
-                UserPage::RatedFilms(_) => TitleType::Movie,
-                UserPage::RatedShows(_) => TitleType::Show,
-                UserPage::Watchlist(_) => {
-                    if url.contains(".pl/serial/") {
-                        TitleType::Show
-                    } else {
-                        TitleType::Movie
-                    }
-                }
-            };
-
-            let (rating, is_favorited, is_watchlisted) = {
-                let api_response = match page {
-                    UserPage::RatedFilms(_) => Some(
-                        self.fw_client_pool
-                            .get(format!(
-                                "https://www.filmweb.pl/api/v1/logged/vote/film/{}/details",
-                                id
-                            ))
-                            .send(),
-                    ),
-                    UserPage::RatedShows(_) => Some(
-                        self.fw_client_pool
-                            .get(format!(
-                                "https://www.filmweb.pl/api/v1/logged/vote/serial/{}/details",
-                                id
-                            ))
-                            .send(),
-                    ),
-                    UserPage::Watchlist(_) => None,
-                };
-
-                // TODO: get rid of these unwraps
-                match api_response {
-                    Some(response) => {
-                        let response = response.unwrap().text().unwrap();
-                        let json: Result<FilmwebApiDetails, _> = serde_json::from_str(&response);
-                        match json {
-                            Ok(s) => (Some(s.rate), s.favorite.unwrap_or(false), false),
-                            Err(e) => {
-                                log::info!("Bad Filmweb's api response: {response}\n{e}");
-                                return Err(FilmwebErrors::InvalidJwt);
-                            }
-                        }
-                    }
-                    None => (None, false, true),
-                }
-            };
-
-            let unrated_title = FilmwebTitle {
-                id: TitleID::FilmwebID(id),
-                url: url.clone(),
-                title_type,
-                fw_genres,
-                genres: OnceCell::new(),
-                name,
-                year,
-                alter_titles: Some(alter_titles),
-                duration,
-                imdb_data: None,
-            };
-
-            rated_titles.push(FilmwebRatedTitle::new(
-                unrated_title,
-                rating,
-                is_favorited,
-                is_watchlisted,
-            ));
+    /// Writes changed cookies back to `credentials` from now on, and right away if they've
+    /// changed since `loaded` were read from it
+    pub(super) fn with_credentials(
+        mut self,
+        credentials: StoredCredentials,
+        loaded: &FilmwebCookies,
+    ) -> Self {
+        if self.cookies() != *loaded {
+            credentials.save(&self.cookies());
         }
+        self.credentials = Some(credentials);
+        self
+    }
 
-        Ok(RatedPage { rated_titles })
+    /// Brings back a saved session, failing with [`FilmwebErrors::InvalidCredentials`] when its
+    /// cookies no longer log in as its user. Counts are taken from the session as they were
+    pub fn from_session(session: FilmwebSession) -> Result<Self, FilmwebErrors> {
+        Self::builder().restore(session)
     }
 
-    fn fetch_rated_count(
-        username: &str,
-        title_type: &'static str,
-        title_type2: &'static str,
-        fw_client: &FilmwebUserHttpClient,
-    ) -> Result<u16, FilmwebErrors> {
-        let url = format!(
-            "https://www.filmweb.pl/api/v1/user/{}/{}/{}/count",
-            username, title_type, title_type2
-        );
-        Ok(fw_client.get(url).send().unwrap().text()?.parse::<u16>()?)
-    }
-
-    fn rated_counts(
-        username: &str,
-        fw_client: &FilmwebUserHttpClient,
-    ) -> Result<FilmwebUserCounts, FilmwebErrors> {
-        let rated_movies_count = Self::fetch_rated_count(username, "votes", "film", fw_client)?;
-        let rated_shows_count = Self::fetch_rated_count(username, "votes", "serial", fw_client)?;
-        let watchlisted_count = Self::fetch_rated_count(username, "want2see", "film", fw_client)?
-            + Self::fetch_rated_count(username, "want2see", "serial", fw_client)?;
-
-        Ok(FilmwebUserCounts {
-            movies: rated_movies_count,
-            shows: rated_shows_count,
-            watchlist: watchlisted_count,
-        })
+    pub(super) fn restored(
+        transport: Arc<dyn Transport>,
+        metrics: Metrics,
+        session: FilmwebSession,
+        base_url: String,
+    ) -> Result<Self, FilmwebErrors> {
+        let user = Self {
+            transport,
+            metrics,
+            cookies: RwLock::new(session.cookies),
+            base_url,
+            username: session.username,
+            counts: session.counts,
+            credentials: None,
+        };
+        if user.get_username()? != user.username {
+            return Err(FilmwebErrors::InvalidCredentials);
+        }
+        Ok(user)
+    }
+
+    /// Current cookies, username and counts, to be saved and restored with
+    /// [`FilmwebUser::from_session`]
+    #[must_use]
+    pub fn session(&self) -> FilmwebSession {
+        FilmwebSession::new(self.cookies(), self.username.clone(), self.counts.clone())
+    }
+
+    /// Origin that all requests are sent to
+    #[must_use]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Requests sent so far, per endpoint
+    #[must_use]
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// Cookies sent along requests, with the JWT renewed when needed
+    #[must_use]
+    pub fn cookies(&self) -> FilmwebCookies {
+        self.cookies.read().unwrap().clone()
+    }
+
+    /// Claims of the current JWT, e.g. when it expires
+    pub fn jwt_claims(&self) -> Result<JwtClaims, FilmwebErrors> {
+        self.cookies.read().unwrap().claims()
+    }
+
+    /// Logs a warning and returns `true` if the JWT expires before `duration` passes. Worth
+    /// checking before a long scrape, as the JWT then has to be renewed midway
+    pub fn warn_if_jwt_expires_within(&self, duration: Duration) -> Result<bool, FilmwebErrors> {
+        Ok(warn_if_expires_within(&self.jwt_claims()?, duration))
     }
 
-    fn get_username(fw_client: &FilmwebUserHttpClient) -> Result<String, FilmwebErrors> {
-        let res = fw_client
-            .get("https://www.filmweb.pl/settings")
-            .send()
+    /// Sends a GET request with user's cookies, and takes the cookies the response rotates
+    fn get(&self, url: &str) -> Result<HttpResponse, TransportError> {
+        let request = self
+            .cookies
+            .read()
             .unwrap()
-            .text()
-            .unwrap();
-        let document = Html::parse_document(&res);
-        document
-            .select(&Selector::parse(".mainSettings__groupItemStateContent").unwrap())
-            .nth(2)
-            .map_or_else(
-                || Err(FilmwebErrors::InvalidCredentials),
-                |username_tag| Ok(username_tag.inner_html().trim().to_owned()),
-            )
+            .authenticate(HttpRequest::get(url));
+        let response = self.transport.execute(request)?;
+        keep_rotated(&self.cookies, self.credentials.as_ref(), &response);
+        Ok(response)
+    }
+
+    /// Replaces an expired JWT with a new one, obtained with `_fwuser_token`. Called on its own
+    /// when a scrape runs into an expired JWT
+    pub fn renew_jwt(&self) -> Result<(), FilmwebErrors> {
+        let cookies = self.cookies();
+        let request = login::renewal_request(&self.base_url, &cookies);
+        let url = request.url.clone();
+        let response = self.transport.execute(request)?;
+        let renewed = login::parse_renewal(url, &cookies, &response)?;
+        replace_cookies(&self.cookies, self.credentials.as_ref(), renewed);
+        log::info!("Renewed the JWT of {}", self.username);
+        Ok(())
+    }
+
+    /// Every rated film, fetched page by page, see [`RatedTitles`]
+    pub fn rated_films(&self) -> RatedTitles<'_> {
+        self.library(UserPageType::RatedFilms)
+    }
+
+    /// Every rated serial, fetched page by page, see [`RatedTitles`]
+    pub fn rated_shows(&self) -> RatedTitles<'_> {
+        self.library(UserPageType::RatedShows)
+    }
+
+    /// Every rated game, fetched page by page, see [`RatedTitles`]
+    pub fn rated_games(&self) -> RatedTitles<'_> {
+        self.library(UserPageType::RatedGames)
+    }
+
+    /// Every title the user wants to see, fetched page by page, see [`RatedTitles`]
+    pub fn watchlist(&self) -> RatedTitles<'_> {
+        self.library(UserPageType::Watchlist)
+    }
+
+    /// Every title the user wants to see, in `order`
+    pub fn sorted_watchlist(
+        &self,
+        order: WatchlistOrder,
+    ) -> Result<Vec<FilmwebRatedTitle>, FilmwebErrors> {
+        let mut titles = self.watchlist().collect::<Result<Vec<_>, _>>()?;
+        order.sort(&mut titles);
+        Ok(titles)
+    }
+
+    fn library(&self, page_type: UserPageType) -> RatedTitles<'_> {
+        library::rated_titles(self, page_type, self.counts.of(page_type))
+    }
+
+    /// Scrapes a user page, together with the vote of every title on it. Alternate titles and
+    /// duration are fetched the first time they're needed. That's a request or a few per title,
+    /// [`FilmwebUser::rated_films`] and the like fetch a whole library with far fewer
+    pub fn scrape(&self, page: UserPage) -> Result<RatedPage, FilmwebErrors> {
+        library::scrape(self, page)
+    }
+
+    fn get_username(&self) -> Result<String, FilmwebErrors> {
+        let res = self.get(&format!("{}/settings", self.base_url))?.text();
+        parse_username(&res)
+    }
+}
+
+impl UserApi for FilmwebUser {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn owner(&self) -> ListOwner<'_> {
+        ListOwner::LoggedIn
+    }
+
+    fn transport(&self) -> &Arc<dyn Transport> {
+        &self.transport
+    }
+
+    fn get_text(&self, url: &str) -> Result<String, FilmwebErrors> {
+        Ok(self.get(url)?.text())
+    }
+
+    /// Fetches a response of the logged-in api, e.g. user's rating of a title, retrying once
+    /// with a renewed JWT if it has expired
+    fn get_renewing<T: DeserializeOwned>(&self, url: &str) -> Result<String, FilmwebErrors> {
+        let response = self.get(url)?;
+        if !is_jwt_expired::<T>(&response) {
+            return Ok(response.text());
+        }
+        self.renew_jwt()?;
+        Ok(self.get(url)?.text())
     }
 }
 
@@ -522,7 +1055,483 @@ impl User for FilmwebUser {
         self.counts.shows
     }
 
+    fn num_of_rated_games(&self) -> u16 {
+        self.counts.games
+    }
+
     fn num_of_watchlisted_titles(&self) -> u16 {
         self.counts.watchlist
     }
 }
+
+/// Async flavour of [`FilmwebUser`], sharing its parsing code
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct AsyncFilmwebUser {
+    transport: Arc<dyn AsyncTransport>,
+    cookies: RwLock<FilmwebCookies>,
+    base_url: String,
+    metrics: Metrics,
+    username: String,
+    counts: FilmwebUserCounts,
+    credentials: Option<StoredCredentials>,
+}
+
+#[cfg(feature = "async")]
+impl AsyncFilmwebUser {
+    pub async fn new<T: ToString>(token: T, session: T, jwt: T) -> Result<Self, FilmwebErrors> {
+        FilmwebUserBuilder::new()
+            .build_async(token, session, jwt)
+            .await
+    }
+
+    /// See [`FilmwebUser::from_cookies_file`]
+    pub async fn from_cookies_file<P: AsRef<Path>>(path: P) -> Result<Self, FilmwebErrors> {
+        FilmwebUserBuilder::new()
+            .build_with_cookies_async(FilmwebCookies::load(path)?)
+            .await
+    }
+
+    pub async fn login(username: &str, password: &str) -> Result<Self, FilmwebErrors> {
+        FilmwebUserBuilder::new()
+            .login_async(username, password)
+            .await
+    }
+
+    /// See [`FilmwebUser::from_store`]
+    pub async fn from_store<S: CredentialStore + 'static>(
+        store: S,
+        account: &str,
+    ) -> Result<Self, FilmwebErrors> {
+        FilmwebUserBuilder::new()
+            .build_from_store_async(store, account)
+            .await
+    }
+
+    /// See [`FilmwebUser::with_credentials`]
+    pub(super) fn with_credentials(
+        mut self,
+        credentials: StoredCredentials,
+        loaded: &FilmwebCookies,
+    ) -> Self {
+        if self.cookies() != *loaded {
+            credentials.save(&self.cookies());
+        }
+        self.credentials = Some(credentials);
+        self
+    }
+
+    pub(super) async fn with_transport(
+        transport: Arc<dyn AsyncTransport>,
+        metrics: Metrics,
+        cookies: FilmwebCookies,
+        base_url: String,
+    ) -> Result<Self, FilmwebErrors> {
+        let mut user = Self {
+            transport,
+            metrics,
+            cookies: RwLock::new(cookies),
+            base_url,
+            username: String::new(),
+            counts: FilmwebUserCounts::default(),
+            credentials: None,
+        };
+        user.username = user.get_username().await?;
+        user.counts = library::rated_counts_async(&user).await?;
+        Ok(user)
+    }
+
+    /// See [`FilmwebUser::from_session`]
+    pub async fn from_session(session: FilmwebSession) -> Result<Self, FilmwebErrors> {
+        FilmwebUserBuilder::new().restore_async(session).await
+    }
+
+    pub(super) async fn restored(
+        transport: Arc<dyn AsyncTransport>,
+        metrics: Metrics,
+        session: FilmwebSession,
+        base_url: String,
+    ) -> Result<Self, FilmwebErrors> {
+        let user = Self {
+            transport,
+            metrics,
+            cookies: RwLock::new(session.cookies),
+            base_url,
+            username: session.username,
+            counts: session.counts,
+            credentials: None,
+        };
+        if user.get_username().await? != user.username {
+            return Err(FilmwebErrors::InvalidCredentials);
+        }
+        Ok(user)
+    }
+
+    /// See [`FilmwebUser::session`]
+    #[must_use]
+    pub fn session(&self) -> FilmwebSession {
+        FilmwebSession::new(self.cookies(), self.username.clone(), self.counts.clone())
+    }
+
+    /// Origin that all requests are sent to
+    #[must_use]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Requests sent so far, per endpoint
+    #[must_use]
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// See [`FilmwebUser::cookies`]
+    #[must_use]
+    pub fn cookies(&self) -> FilmwebCookies {
+        self.cookies.read().unwrap().clone()
+    }
+
+    /// See [`FilmwebUser::jwt_claims`]
+    pub fn jwt_claims(&self) -> Result<JwtClaims, FilmwebErrors> {
+        self.cookies.read().unwrap().claims()
+    }
+
+    /// See [`FilmwebUser::warn_if_jwt_expires_within`]
+    pub fn warn_if_jwt_expires_within(&self, duration: Duration) -> Result<bool, FilmwebErrors> {
+        Ok(warn_if_expires_within(&self.jwt_claims()?, duration))
+    }
+
+    /// Sends a GET request with user's cookies, and takes the cookies the response rotates
+    async fn get(&self, url: &str) -> Result<HttpResponse, TransportError> {
+        let request = self
+            .cookies
+            .read()
+            .unwrap()
+            .authenticate(HttpRequest::get(url));
+        let response = self.transport.execute(request).await?;
+        keep_rotated(&self.cookies, self.credentials.as_ref(), &response);
+        Ok(response)
+    }
+
+    /// See [`FilmwebUser::renew_jwt`]
+    pub async fn renew_jwt(&self) -> Result<(), FilmwebErrors> {
+        let cookies = self.cookies();
+        let request = login::renewal_request(&self.base_url, &cookies);
+        let url = request.url.clone();
+        let response = self.transport.execute(request).await?;
+        let renewed = login::parse_renewal(url, &cookies, &response)?;
+        replace_cookies(&self.cookies, self.credentials.as_ref(), renewed);
+        log::info!("Renewed the JWT of {}", self.username);
+        Ok(())
+    }
+
+    /// Every rated film, see [`FilmwebUser::rated_films`]
+    pub async fn rated_films(&self) -> Result<Vec<FilmwebRatedTitle>, FilmwebErrors> {
+        self.fetch_library(UserPageType::RatedFilms).await
+    }
+
+    /// Every rated serial, see [`FilmwebUser::rated_shows`]
+    pub async fn rated_shows(&self) -> Result<Vec<FilmwebRatedTitle>, FilmwebErrors> {
+        self.fetch_library(UserPageType::RatedShows).await
+    }
+
+    /// Every rated game, see [`FilmwebUser::rated_games`]
+    pub async fn rated_games(&self) -> Result<Vec<FilmwebRatedTitle>, FilmwebErrors> {
+        self.fetch_library(UserPageType::RatedGames).await
+    }
+
+    /// Every title the user wants to see, see [`FilmwebUser::watchlist`]
+    pub async fn watchlist(&self) -> Result<Vec<FilmwebRatedTitle>, FilmwebErrors> {
+        self.fetch_library(UserPageType::Watchlist).await
+    }
+
+    /// See [`FilmwebUser::sorted_watchlist`]
+    pub async fn sorted_watchlist(
+        &self,
+        order: WatchlistOrder,
+    ) -> Result<Vec<FilmwebRatedTitle>, FilmwebErrors> {
+        let mut titles = self.watchlist().await?;
+        order.sort(&mut titles);
+        Ok(titles)
+    }
+
+    async fn fetch_library(
+        &self,
+        page_type: UserPageType,
+    ) -> Result<Vec<FilmwebRatedTitle>, FilmwebErrors> {
+        library::fetch_library_async(self, page_type, self.counts.of(page_type)).await
+    }
+
+    /// Scrapes a user page, see [`FilmwebUser::scrape`]
+    pub async fn scrape(&self, page: UserPage) -> Result<RatedPage, FilmwebErrors> {
+        library::scrape_async(self, page).await
+    }
+
+    async fn get_username(&self) -> Result<String, FilmwebErrors> {
+        let res = self
+            .get(&format!("{}/settings", self.base_url))
+            .await?
+            .text();
+        parse_username(&res)
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncUserApi for AsyncFilmwebUser {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn owner(&self) -> ListOwner<'_> {
+        ListOwner::LoggedIn
+    }
+
+    fn transport(&self) -> &Arc<dyn AsyncTransport> {
+        &self.transport
+    }
+
+    async fn get_text(&self, url: &str) -> Result<String, FilmwebErrors> {
+        Ok(self.get(url).await?.text())
+    }
+
+    /// Like the one of [`FilmwebUser`]
+    async fn get_renewing<T: DeserializeOwned>(&self, url: &str) -> Result<String, FilmwebErrors> {
+        let response = self.get(url).await?;
+        if !is_jwt_expired::<T>(&response) {
+            return Ok(response.text());
+        }
+        self.renew_jwt().await?;
+        Ok(self.get(url).await?.text())
+    }
+}
+
+#[cfg(feature = "async")]
+impl User for AsyncFilmwebUser {
+    fn username(&self) -> &String {
+        &self.username
+    }
+
+    fn num_of_rated_movies(&self) -> u16 {
+        self.counts.movies
+    }
+
+    fn num_of_rated_shows(&self) -> u16 {
+        self.counts.shows
+    }
+
+    fn num_of_rated_games(&self) -> u16 {
+        self.counts.games
+    }
+
+    fn num_of_watchlisted_titles(&self) -> u16 {
+        self.counts.watchlist
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_vote_details() {
+        let details = r#"{"rate":8,"favorite":true,"viewDate":20230115,"timestamp":1673788800000}"#;
+        let vote = parse_vote_details(UserPage::RatedFilms(1), details).unwrap();
+        assert_eq!(
+            (vote.rating, vote.favorited, vote.watchlisted),
+            (Some(8), Some(true), false)
+        );
+        assert_eq!(
+            vote.rated_at,
+            Some(OffsetDateTime::from_unix_timestamp(1_673_788_800).unwrap())
+        );
+        assert_eq!(
+            vote.view_date,
+            Some(Date::from_calendar_date(2023, Month::January, 15).unwrap())
+        );
+        let details = r#"{"rate":5,"viewDate":0,"timestamp":0}"#;
+        let vote = parse_vote_details(UserPage::RatedFilms(1), details).unwrap();
+        assert_eq!(
+            (vote.rating, vote.favorited, vote.watchlisted),
+            (Some(5), Some(false), false)
+        );
+        assert_eq!((vote.rated_at, vote.view_date), (None, None));
+        assert_eq!(vote.comment, None);
+        let details = r#"{"rate":9,"viewDate":0,"timestamp":0,"comment":" Świetny ","commentTimestamp":1673788800000,"commentLikes":3}"#;
+        let comment = parse_vote_details(UserPage::RatedFilms(1), details)
+            .unwrap()
+            .comment
+            .unwrap();
+        assert_eq!(comment.text, "Świetny");
+        assert_eq!(
+            comment.written_at,
+            Some(OffsetDateTime::from_unix_timestamp(1_673_788_800).unwrap())
+        );
+        assert_eq!(comment.likes, 3);
+        let details = r#"{"rate":9,"viewDate":0,"timestamp":0,"comment":"  "}"#;
+        let vote = parse_vote_details(UserPage::RatedFilms(1), details).unwrap();
+        assert_eq!(vote.comment, None);
+        let details = r#"{"level":4,"timestamp":1673788800000}"#;
+        let vote = parse_vote_details(UserPage::Watchlist(1), details).unwrap();
+        assert_eq!(
+            (vote.rating, vote.favorited, vote.watchlisted),
+            (None, Some(false), true)
+        );
+        assert_eq!(vote.interest, Some(4));
+        assert_eq!(
+            vote.watchlisted_at,
+            Some(OffsetDateTime::from_unix_timestamp(1_673_788_800).unwrap())
+        );
+        assert_eq!(
+            parse_vote_details(UserPage::Watchlist(1), "{}").unwrap(),
+            Vote::watchlisted(Some(false), 0, 0)
+        );
+        assert!(matches!(
+            parse_vote_details(UserPage::RatedShows(1), "<html></html>"),
+            Err(FilmwebErrors::InvalidJwt)
+        ));
+    }
+
+    #[test]
+    fn creating_fwuser_with_fake_transport() {
+        use crate::http::testing::FakeTransport;
+        use crate::http::Endpoint;
+
+        let settings = r#"<div class="mainSettings__groupItemStateContent">a</div>
+            <div class="mainSettings__groupItemStateContent">b</div>
+            <div class="mainSettings__groupItemStateContent"> tester </div>"#;
+        let transport = Arc::new(FakeTransport::new(vec![
+            ("/settings", settings),
+            ("/votes/film/count", "12"),
+            ("/votes/serial/count", "3"),
+            ("/votes/game/count", "7"),
+            ("/want2see/film/count", "4"),
+            ("/want2see/serial/count", "1"),
+        ]));
+        let user = FilmwebUser::builder()
+            .base_url("http://filmweb.test")
+            .transport(Arc::clone(&transport))
+            .build("token", "session", "jwt")
+            .unwrap();
+
+        assert_eq!(user.username(), "tester");
+        assert_eq!(user.num_of_rated_movies(), 12);
+        assert_eq!(user.num_of_rated_shows(), 3);
+        assert_eq!(user.num_of_rated_games(), 7);
+        assert_eq!(user.num_of_watchlisted_titles(), 5);
+        assert_eq!(user.metrics().get(Endpoint::Other).requests, 6);
+
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(
+            requests[1].url,
+            "http://filmweb.test/api/v1/user/tester/votes/film/count"
+        );
+        assert!(requests.iter().all(|request| request.headers
+            == vec![(
+                "Cookie".to_owned(),
+                "_fwuser_token=token; _fwuser_sessionId=session; JWT=jwt;".to_owned()
+            )]));
+    }
+
+    #[test]
+    fn restoring_sessions() {
+        use crate::http::testing::FakeTransport;
+
+        let settings = r#"<div class="mainSettings__groupItemStateContent">a</div>
+            <div class="mainSettings__groupItemStateContent">b</div>
+            <div class="mainSettings__groupItemStateContent">tester</div>"#;
+        let transport = Arc::new(FakeTransport::new(vec![("/settings", settings)]));
+        let restore = |username: &str| {
+            let session = FilmwebSession::new(
+                FilmwebCookies::new("token", "session", "jwt"),
+                username.to_owned(),
+                FilmwebUserCounts {
+                    movies: 7,
+                    ..FilmwebUserCounts::default()
+                },
+            );
+            FilmwebUser::builder()
+                .base_url("http://filmweb.test")
+                .transport(Arc::clone(&transport))
+                .restore(session)
+        };
+
+        let user = restore("tester").unwrap();
+        assert_eq!(user.num_of_rated_movies(), 7);
+        assert_eq!(transport.requests.lock().unwrap().len(), 1);
+        assert!(matches!(
+            restore("someone else"),
+            Err(FilmwebErrors::InvalidCredentials)
+        ));
+    }
+
+    #[test]
+    fn taking_rotated_cookies() {
+        use crate::http::testing::{response, FakeTransport};
+
+        let settings = r#"<div class="mainSettings__groupItemStateContent">a</div>
+            <div class="mainSettings__groupItemStateContent">b</div>
+            <div class="mainSettings__groupItemStateContent">tester</div>"#;
+        let mut rotating = response(200, "1");
+        rotating.headers.push((
+            "Set-Cookie".to_owned(),
+            "_fwuser_sessionId=rotated; Path=/; HttpOnly".to_owned(),
+        ));
+        let transport = Arc::new(
+            FakeTransport::new(vec![("/settings", settings), ("/count", "0")])
+                .with_response("/votes/film/count", rotating),
+        );
+        let user = FilmwebUser::builder()
+            .base_url("http://filmweb.test")
+            .transport(Arc::clone(&transport))
+            .build("token", "session", "jwt")
+            .unwrap();
+
+        assert_eq!(
+            user.cookies(),
+            FilmwebCookies::new("token", "rotated", "jwt")
+        );
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(
+            requests.last().unwrap().headers[0].1,
+            "_fwuser_token=token; _fwuser_sessionId=rotated; JWT=jwt;"
+        );
+    }
+
+    #[test]
+    fn renewing_expired_jwt() {
+        use crate::http::testing::{response, FakeTransport};
+
+        let settings = r#"<div class="mainSettings__groupItemStateContent">a</div>
+            <div class="mainSettings__groupItemStateContent">b</div>
+            <div class="mainSettings__groupItemStateContent">tester</div>"#;
+        let mut renewal = response(200, "");
+        renewal
+            .headers
+            .push(("Set-Cookie".to_owned(), "JWT=renewed; Path=/".to_owned()));
+        let transport = Arc::new(
+            FakeTransport::new(vec![("/settings", settings), ("/count", "0")])
+                .with_status("/details", 401, "<html></html>")
+                .with_response("/api/v1/jwt", renewal),
+        );
+        let user = FilmwebUser::builder()
+            .base_url("http://filmweb.test")
+            .transport(Arc::clone(&transport))
+            .build("token", "session", "expired")
+            .unwrap();
+
+        let url = "http://filmweb.test/api/v1/logged/vote/film/1/details";
+        user.get_renewing::<FilmwebApiDetails>(url).unwrap();
+        assert_eq!(user.cookies().jwt, "renewed");
+        let session = user.session();
+        assert_eq!(session.cookies.jwt, "renewed");
+
+        let requests = transport.requests.lock().unwrap();
+        let retried = requests.last().unwrap();
+        assert_eq!(retried.url, url);
+        assert!(retried.headers[0].1.contains("JWT=renewed;"));
+        assert_eq!(
+            requests[requests.len() - 2].headers[0].1,
+            "_fwuser_token=token; _fwuser_sessionId=session;"
+        );
+    }
+}
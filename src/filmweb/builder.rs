@@ -0,0 +1,248 @@
+use super::auth::FilmwebCookies;
+use super::credentials::{CredentialStore, StoredCredentials};
+use super::login;
+#[cfg(feature = "async")]
+use super::{AsyncFilmweb, AsyncFilmwebUser, AsyncPublicFilmwebUser};
+use super::{Filmweb, FilmwebSession, FilmwebUser, PublicFilmwebUser, FILMWEB_BASE_URL};
+use crate::error::{BuildError, FilmwebErrors};
+use crate::http::{transport_setters, TransportOptions};
+use crate::utils::parse_base_url;
+
+use std::sync::Arc;
+
+/// Builder of [`Filmweb`] (and `AsyncFilmweb`)
+#[derive(Debug, Clone)]
+pub struct FilmwebBuilder {
+    base_url: String,
+    options: TransportOptions,
+}
+
+impl FilmwebBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            base_url: FILMWEB_BASE_URL.to_owned(),
+            options: TransportOptions::new(),
+        }
+    }
+
+    /// Sends every request to a different origin than <https://www.filmweb.pl>
+    #[must_use]
+    pub fn base_url<T: Into<String>>(mut self, base_url: T) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    transport_setters!();
+
+    pub fn build(self) -> Result<Filmweb, BuildError> {
+        Ok(Filmweb {
+            base_url: parse_base_url(&self.base_url)?,
+            metrics: self.options.metrics.clone(),
+            transport: self.options.build()?,
+        })
+    }
+
+    #[cfg(feature = "async")]
+    pub fn build_async(self) -> Result<AsyncFilmweb, BuildError> {
+        Ok(AsyncFilmweb {
+            base_url: parse_base_url(&self.base_url)?,
+            metrics: self.options.metrics.clone(),
+            transport: self.options.build_async()?,
+        })
+    }
+}
+
+impl Default for FilmwebBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builder of [`FilmwebUser`] and [`PublicFilmwebUser`] (and their async flavours). Building a
+/// user sends requests, as its username and counts are fetched right away
+#[derive(Debug, Clone)]
+pub struct FilmwebUserBuilder {
+    base_url: String,
+    options: TransportOptions,
+}
+
+impl FilmwebUserBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            base_url: FILMWEB_BASE_URL.to_owned(),
+            options: TransportOptions::new(),
+        }
+    }
+
+    /// Sends every request to a different origin than <https://www.filmweb.pl>
+    #[must_use]
+    pub fn base_url<T: Into<String>>(mut self, base_url: T) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    transport_setters!();
+
+    /// Logs in with `_fwuser_token`, `_fwuser_sessionId` and `JWT` cookie values
+    pub fn build<T: ToString>(
+        self,
+        token: T,
+        session: T,
+        jwt: T,
+    ) -> Result<FilmwebUser, FilmwebErrors> {
+        let base_url = parse_base_url(&self.base_url)?;
+        let metrics = self.options.metrics.clone();
+        let transport = self.options.build()?;
+        FilmwebUser::with_transport(
+            transport,
+            metrics,
+            FilmwebCookies::new(token, session, jwt),
+            base_url,
+        )
+    }
+
+    /// Logs in with username and password, fails with
+    /// [`CaptchaRequired`](FilmwebErrors::CaptchaRequired) or
+    /// [`TwoFactorRequired`](FilmwebErrors::TwoFactorRequired) when Filmweb challenges the login
+    pub fn login(self, username: &str, password: &str) -> Result<FilmwebUser, FilmwebErrors> {
+        let base_url = parse_base_url(&self.base_url)?;
+        let metrics = self.options.metrics.clone();
+        let transport = self.options.build()?;
+        let cookies = login::login(transport.as_ref(), &base_url, username, password)?;
+        FilmwebUser::with_transport(transport, metrics, cookies, base_url)
+    }
+
+    /// Brings back a saved session, see [`FilmwebUser::from_session`]
+    pub fn restore(self, session: FilmwebSession) -> Result<FilmwebUser, FilmwebErrors> {
+        let base_url = parse_base_url(&self.base_url)?;
+        let metrics = self.options.metrics.clone();
+        let transport = self.options.build()?;
+        FilmwebUser::restored(transport, metrics, session, base_url)
+    }
+
+    /// Logs in with cookies read e.g. by [`FilmwebCookies::load`]
+    pub fn build_with_cookies(self, cookies: FilmwebCookies) -> Result<FilmwebUser, FilmwebErrors> {
+        let base_url = parse_base_url(&self.base_url)?;
+        let metrics = self.options.metrics.clone();
+        let transport = self.options.build()?;
+        FilmwebUser::with_transport(transport, metrics, cookies, base_url)
+    }
+
+    /// Browses a public profile without logging in, see [`PublicFilmwebUser`]
+    pub fn build_public<T: Into<String>>(
+        self,
+        username: T,
+    ) -> Result<PublicFilmwebUser, FilmwebErrors> {
+        let base_url = parse_base_url(&self.base_url)?;
+        let metrics = self.options.metrics.clone();
+        let transport = self.options.build()?;
+        PublicFilmwebUser::with_transport(transport, metrics, username.into(), base_url)
+    }
+
+    /// Logs in with cookies of `account` kept in `store`. Whenever the cookies change, e.g. the
+    /// JWT is renewed or Filmweb rotates the session, the new ones are written back to `store`
+    pub fn build_from_store<S: CredentialStore + 'static>(
+        self,
+        store: S,
+        account: &str,
+    ) -> Result<FilmwebUser, FilmwebErrors> {
+        let credentials = StoredCredentials::new(Arc::new(store), account);
+        let cookies = credentials.load()?;
+        Ok(self
+            .build_with_cookies(cookies.clone())?
+            .with_credentials(credentials, &cookies))
+    }
+
+    /// Async flavour of [`FilmwebUserBuilder::build`]
+    #[cfg(feature = "async")]
+    pub async fn build_async<T: ToString>(
+        self,
+        token: T,
+        session: T,
+        jwt: T,
+    ) -> Result<AsyncFilmwebUser, FilmwebErrors> {
+        let base_url = parse_base_url(&self.base_url)?;
+        let metrics = self.options.metrics.clone();
+        let transport = self.options.build_async()?;
+        AsyncFilmwebUser::with_transport(
+            transport,
+            metrics,
+            FilmwebCookies::new(token, session, jwt),
+            base_url,
+        )
+        .await
+    }
+
+    /// Async flavour of [`FilmwebUserBuilder::build_with_cookies`]
+    #[cfg(feature = "async")]
+    pub async fn build_with_cookies_async(
+        self,
+        cookies: FilmwebCookies,
+    ) -> Result<AsyncFilmwebUser, FilmwebErrors> {
+        let base_url = parse_base_url(&self.base_url)?;
+        let metrics = self.options.metrics.clone();
+        let transport = self.options.build_async()?;
+        AsyncFilmwebUser::with_transport(transport, metrics, cookies, base_url).await
+    }
+
+    /// Async flavour of [`FilmwebUserBuilder::build_from_store`]
+    #[cfg(feature = "async")]
+    pub async fn build_from_store_async<S: CredentialStore + 'static>(
+        self,
+        store: S,
+        account: &str,
+    ) -> Result<AsyncFilmwebUser, FilmwebErrors> {
+        let credentials = StoredCredentials::new(Arc::new(store), account);
+        let cookies = credentials.load()?;
+        Ok(self
+            .build_with_cookies_async(cookies.clone())
+            .await?
+            .with_credentials(credentials, &cookies))
+    }
+
+    /// Async flavour of [`FilmwebUserBuilder::restore`]
+    #[cfg(feature = "async")]
+    pub async fn restore_async(
+        self,
+        session: FilmwebSession,
+    ) -> Result<AsyncFilmwebUser, FilmwebErrors> {
+        let base_url = parse_base_url(&self.base_url)?;
+        let metrics = self.options.metrics.clone();
+        let transport = self.options.build_async()?;
+        AsyncFilmwebUser::restored(transport, metrics, session, base_url).await
+    }
+
+    /// Async flavour of [`FilmwebUserBuilder::build_public`]
+    #[cfg(feature = "async")]
+    pub async fn build_public_async<T: Into<String>>(
+        self,
+        username: T,
+    ) -> Result<AsyncPublicFilmwebUser, FilmwebErrors> {
+        let base_url = parse_base_url(&self.base_url)?;
+        let metrics = self.options.metrics.clone();
+        let transport = self.options.build_async()?;
+        AsyncPublicFilmwebUser::with_transport(transport, metrics, username.into(), base_url).await
+    }
+
+    /// Async flavour of [`FilmwebUserBuilder::login`]
+    #[cfg(feature = "async")]
+    pub async fn login_async(
+        self,
+        username: &str,
+        password: &str,
+    ) -> Result<AsyncFilmwebUser, FilmwebErrors> {
+        let base_url = parse_base_url(&self.base_url)?;
+        let metrics = self.options.metrics.clone();
+        let transport = self.options.build_async()?;
+        let cookies = login::login_async(transport.as_ref(), &base_url, username, password).await?;
+        AsyncFilmwebUser::with_transport(transport, metrics, cookies, base_url).await
+    }
+}
+
+impl Default for FilmwebUserBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,178 @@
+//! Reading [`FilmwebCookies`] from files exported by browsers, either in the Netscape
+//! `cookies.txt` format or as JSON of cookie-export extensions (EditThisCookie, Cookie-Editor).
+
+use super::auth::FilmwebCookies;
+use crate::error::CookieImportError;
+
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+
+const NAMES: [&str; 3] = ["_fwuser_token", "_fwuser_sessionId", "JWT"];
+
+/// A cookie as exported, expiry in seconds since the epoch
+#[derive(Deserialize, Debug)]
+struct ExportedCookie {
+    #[serde(default)]
+    domain: String,
+    name: String,
+    value: String,
+    #[serde(
+        default,
+        rename = "expirationDate",
+        alias = "expires",
+        alias = "expiry"
+    )]
+    expiration_date: Option<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Export {
+    Cookies(Vec<ExportedCookie>),
+    Wrapped { cookies: Vec<ExportedCookie> },
+}
+
+impl FilmwebCookies {
+    /// Reads a file in either supported format, told apart by its first character
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, CookieImportError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|source| CookieImportError::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        if contents.trim_start().starts_with(['[', '{']) {
+            Self::from_json(&contents)
+        } else {
+            Self::from_netscape(&contents)
+        }
+    }
+
+    /// Picks filmweb.pl cookies out of a Netscape `cookies.txt`, as written by curl or wget
+    pub fn from_netscape(contents: &str) -> Result<Self, CookieImportError> {
+        let cookies = contents.lines().filter_map(|line| {
+            // curl marks HttpOnly cookies with a prefix that makes them look like comments
+            let line = line.strip_prefix("#HttpOnly_").unwrap_or(line);
+            if line.starts_with('#') {
+                return None;
+            }
+            let fields: Vec<&str> = line.trim_end_matches('\r').split('\t').collect();
+            let [domain, _, _, _, expires, name, value] = fields.as_slice() else {
+                return None;
+            };
+            Some(ExportedCookie {
+                domain: (*domain).to_owned(),
+                name: (*name).to_owned(),
+                value: (*value).to_owned(),
+                expiration_date: expires.parse().ok(),
+            })
+        });
+        pick(cookies)
+    }
+
+    /// Picks filmweb.pl cookies out of a JSON export, a list of cookies or an object with one
+    pub fn from_json(contents: &str) -> Result<Self, CookieImportError> {
+        let cookies = match serde_json::from_str(contents)? {
+            Export::Cookies(cookies) | Export::Wrapped { cookies } => cookies,
+        };
+        pick(cookies)
+    }
+}
+
+fn pick<I: IntoIterator<Item = ExportedCookie>>(
+    cookies: I,
+) -> Result<FilmwebCookies, CookieImportError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |since| since.as_secs_f64());
+    let mut found: [Option<String>; 3] = Default::default();
+    let mut expired = [false; 3];
+    for cookie in cookies {
+        let domain = cookie.domain.trim_start_matches('.');
+        if domain != "filmweb.pl" && !domain.ends_with(".filmweb.pl") {
+            continue;
+        }
+        let Some(index) = NAMES.iter().position(|name| *name == cookie.name) else {
+            continue;
+        };
+        // Session cookies are exported without an expiry or with 0
+        if cookie
+            .expiration_date
+            .is_some_and(|expiry| expiry > 0.0 && expiry < now)
+        {
+            expired[index] = true;
+        } else {
+            found[index] = Some(cookie.value);
+        }
+    }
+
+    if let [Some(token), Some(session), Some(jwt)] = found {
+        return Ok(FilmwebCookies::new(token, session, jwt));
+    }
+    let (mut missing, mut expired_names) = (Vec::new(), Vec::new());
+    for (index, name) in NAMES.into_iter().enumerate() {
+        match (&found[index], expired[index]) {
+            (Some(_), _) => {}
+            (None, true) => expired_names.push(name),
+            (None, false) => missing.push(name),
+        }
+    }
+    Err(CookieImportError::Unusable {
+        missing,
+        expired: expired_names,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reading_netscape_cookies() {
+        let contents = "# Netscape HTTP Cookie File\n\
+            .filmweb.pl\tTRUE\t/\tTRUE\t0\t_fwuser_sessionId\tsession\n\
+            #HttpOnly_.filmweb.pl\tTRUE\t/\tTRUE\t4102444800\t_fwuser_token\ttoken\n\
+            www.filmweb.pl\tFALSE\t/\tTRUE\t0\tJWT\tjwt\n\
+            .example.com\tTRUE\t/\tFALSE\t0\tJWT\tother\n";
+        assert_eq!(
+            FilmwebCookies::from_netscape(contents).unwrap(),
+            FilmwebCookies::new("token", "session", "jwt")
+        );
+
+        let contents = ".filmweb.pl\tTRUE\t/\tTRUE\t1000\t_fwuser_token\ttoken\n\
+            .filmweb.pl\tTRUE\t/\tTRUE\t0\tJWT\tjwt\n";
+        let Err(CookieImportError::Unusable { missing, expired }) =
+            FilmwebCookies::from_netscape(contents)
+        else {
+            panic!("cookies shouldn't be usable");
+        };
+        assert_eq!(missing, vec!["_fwuser_sessionId"]);
+        assert_eq!(expired, vec!["_fwuser_token"]);
+    }
+
+    #[test]
+    fn reading_json_exports() {
+        let contents = r#"[
+            {"domain": ".filmweb.pl", "name": "_fwuser_token", "value": "token", "expirationDate": 4102444800.5},
+            {"domain": ".filmweb.pl", "name": "_fwuser_sessionId", "value": "session", "session": true},
+            {"domain": "www.filmweb.pl", "name": "JWT", "value": "jwt", "expirationDate": 4102444800}
+        ]"#;
+        assert_eq!(
+            FilmwebCookies::from_json(contents).unwrap(),
+            FilmwebCookies::new("token", "session", "jwt")
+        );
+
+        let wrapped = format!(r#"{{"url": "https://www.filmweb.pl", "cookies": {contents}}}"#);
+        assert!(FilmwebCookies::from_json(&wrapped).is_ok());
+        assert!(matches!(
+            FilmwebCookies::from_json("[]"),
+            Err(CookieImportError::Unusable { missing, .. }) if missing.len() == 3
+        ));
+        assert!(matches!(
+            FilmwebCookies::from_json("not json"),
+            Err(CookieImportError::Json { .. })
+        ));
+    }
+}
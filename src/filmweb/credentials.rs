@@ -0,0 +1,161 @@
+//! Keeping [`FilmwebCookies`] of several accounts between runs, instead of in environment
+//! variables. A user built from a store writes its cookies back whenever they change, i.e. when
+//! its JWT is renewed or Filmweb rotates a cookie in a response.
+
+#[cfg(feature = "encrypted-store")]
+mod encrypted;
+
+use super::auth::FilmwebCookies;
+use crate::error::CredentialStoreError;
+
+#[cfg(feature = "encrypted-store")]
+pub use encrypted::EncryptedFileStore;
+
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// Storage of cookies by account name, see
+/// [`FilmwebUserBuilder::build_from_store`](super::FilmwebUserBuilder::build_from_store)
+pub trait CredentialStore: Debug + Send + Sync {
+    /// Cookies of `account`, `None` when none were stored
+    fn load(&self, account: &str) -> Result<Option<FilmwebCookies>, CredentialStoreError>;
+
+    /// Stores cookies of `account`, replacing the previous ones
+    fn store(&self, account: &str, cookies: &FilmwebCookies) -> Result<(), CredentialStoreError>;
+}
+
+impl<T: CredentialStore + ?Sized> CredentialStore for Arc<T> {
+    fn load(&self, account: &str) -> Result<Option<FilmwebCookies>, CredentialStoreError> {
+        (**self).load(account)
+    }
+
+    fn store(&self, account: &str, cookies: &FilmwebCookies) -> Result<(), CredentialStoreError> {
+        (**self).store(account, cookies)
+    }
+}
+
+/// Store and account a user's cookies came from
+#[derive(Debug, Clone)]
+pub(super) struct StoredCredentials {
+    store: Arc<dyn CredentialStore>,
+    account: String,
+}
+
+impl StoredCredentials {
+    pub fn new(store: Arc<dyn CredentialStore>, account: &str) -> Self {
+        Self {
+            store,
+            account: account.to_owned(),
+        }
+    }
+
+    pub fn load(&self) -> Result<FilmwebCookies, CredentialStoreError> {
+        self.store
+            .load(&self.account)?
+            .ok_or_else(|| CredentialStoreError::NotFound {
+                account: self.account.clone(),
+            })
+    }
+
+    /// Writes changed cookies back. A failure isn't fatal, the new cookies still work until the
+    /// end of the run
+    pub fn save(&self, cookies: &FilmwebCookies) {
+        if let Err(e) = self.store.store(&self.account, cookies) {
+            log::warn!("Couldn't store changed cookies of {}: {e}", self.account);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filmweb::auth::UserPage;
+    use crate::filmweb::FilmwebUser;
+    use crate::http::testing::{response, FakeTransport};
+    use crate::User;
+
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Default)]
+    struct MemoryStore(Mutex<HashMap<String, FilmwebCookies>>);
+
+    impl CredentialStore for MemoryStore {
+        fn load(&self, account: &str) -> Result<Option<FilmwebCookies>, CredentialStoreError> {
+            Ok(self.0.lock().unwrap().get(account).cloned())
+        }
+
+        fn store(
+            &self,
+            account: &str,
+            cookies: &FilmwebCookies,
+        ) -> Result<(), CredentialStoreError> {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(account.to_owned(), cookies.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn writing_renewed_cookies_back() {
+        let settings = r#"<div class="mainSettings__groupItemStateContent">a</div>
+            <div class="mainSettings__groupItemStateContent">b</div>
+            <div class="mainSettings__groupItemStateContent">tester</div>"#;
+        let mut renewal = response(200, "");
+        renewal
+            .headers
+            .push(("Set-Cookie".to_owned(), "JWT=renewed; Path=/".to_owned()));
+        let rotating = |name: &str, value: &str| {
+            let mut response = response(200, "0");
+            response.headers.push((
+                "Set-Cookie".to_owned(),
+                format!("{name}={value}; Path=/; HttpOnly"),
+            ));
+            response
+        };
+        let transport = Arc::new(
+            FakeTransport::new(vec![("/settings", settings), ("/count", "0")])
+                .with_response("/api/v1/jwt", renewal)
+                .with_response(
+                    "/votes/film/count",
+                    rotating("_fwuser_sessionId", "rotated"),
+                )
+                .with_response("/want2see/film/count", rotating("_fwuser_token", "new"))
+                .with_response("/films?page=", rotating("JWT", "rotated")),
+        );
+        let store = Arc::new(MemoryStore::default());
+        store
+            .store("main", &FilmwebCookies::new("token", "session", "expired"))
+            .unwrap();
+        let build = |account: &str| {
+            FilmwebUser::builder()
+                .base_url("http://filmweb.test")
+                .transport(Arc::clone(&transport))
+                .build_from_store(Arc::clone(&store), account)
+        };
+
+        // Cookies rotated while logging in are written back as well
+        let user = build("main").unwrap();
+        assert_eq!(user.username(), "tester");
+        assert_eq!(
+            store.load("main").unwrap().unwrap(),
+            FilmwebCookies::new("new", "rotated", "expired")
+        );
+        user.renew_jwt().unwrap();
+        assert_eq!(
+            store.load("main").unwrap().unwrap(),
+            FilmwebCookies::new("new", "rotated", "renewed")
+        );
+        // And so are cookies rotated mid-scrape
+        user.scrape(UserPage::RatedFilms(1)).unwrap();
+        assert_eq!(store.load("main").unwrap().unwrap().jwt, "rotated");
+        assert!(matches!(
+            build("other"),
+            Err(crate::FilmwebErrors::CredentialStoreError {
+                source: CredentialStoreError::NotFound { .. }
+            })
+        ));
+    }
+}
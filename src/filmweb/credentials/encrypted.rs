@@ -0,0 +1,232 @@
+use super::CredentialStore;
+use crate::error::CredentialStoreError;
+use crate::filmweb::auth::FilmwebCookies;
+use crate::utils::write_private_file;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+
+const SALT_LEN: usize = 16;
+
+/// Contents of the file, every field base64 encoded. The ciphertext is JSON of cookies by account
+#[derive(Deserialize, Serialize)]
+struct EncryptedFile {
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Salt of the file and the key derived from it
+type DerivedKey = ([u8; SALT_LEN], Key);
+
+/// [`CredentialStore`] keeping every account in one file, encrypted with ChaCha20-Poly1305 under
+/// a key derived from a passphrase with Argon2id. The file is created on the first write
+///
+/// # Examples
+/// ```rust,no_run
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use filmed::filmweb::{CredentialStore, EncryptedFileStore, FilmwebUser};
+///
+/// let store = EncryptedFileStore::new("credentials.bin", "passphrase");
+/// let user = FilmwebUser::login("username", "password")?;
+/// store.store("main", &user.cookies())?;
+///
+/// let user = FilmwebUser::from_store(store, "main")?;
+/// #     Ok(())
+/// # }
+/// ```
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    passphrase: String,
+    /// Deriving a key takes a while, so it's done once per salt. Also serializes writes
+    key: Mutex<Option<DerivedKey>>,
+}
+
+impl EncryptedFileStore {
+    pub fn new<P: AsRef<Path>, T: Into<String>>(path: P, passphrase: T) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+            passphrase: passphrase.into(),
+            key: Mutex::new(None),
+        }
+    }
+
+    fn corrupted(&self) -> CredentialStoreError {
+        CredentialStoreError::Corrupted {
+            path: self.path.clone(),
+        }
+    }
+
+    fn io_error(&self, source: std::io::Error) -> CredentialStoreError {
+        CredentialStoreError::Io {
+            path: self.path.clone(),
+            source,
+        }
+    }
+
+    fn derive_key(&self, salt: [u8; SALT_LEN]) -> Result<Key, CredentialStoreError> {
+        let mut key = Key::default();
+        Argon2::default()
+            .hash_password_into(self.passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|_| self.corrupted())?;
+        Ok(key)
+    }
+
+    fn key(
+        &self,
+        cached: &mut Option<DerivedKey>,
+        salt: [u8; SALT_LEN],
+    ) -> Result<Key, CredentialStoreError> {
+        match cached {
+            Some((cached_salt, key)) if *cached_salt == salt => Ok(*key),
+            _ => {
+                let key = self.derive_key(salt)?;
+                *cached = Some((salt, key));
+                Ok(key)
+            }
+        }
+    }
+
+    /// Decrypts every account, or returns a new salt and no accounts if the file doesn't exist
+    fn read(
+        &self,
+        cached: &mut Option<DerivedKey>,
+    ) -> Result<([u8; SALT_LEN], BTreeMap<String, FilmwebCookies>), CredentialStoreError> {
+        let contents = match fs::read(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let mut salt = [0; SALT_LEN];
+                OsRng.fill_bytes(&mut salt);
+                return Ok((salt, BTreeMap::new()));
+            }
+            Err(e) => return Err(self.io_error(e)),
+        };
+        let file: EncryptedFile =
+            serde_json::from_slice(&contents).map_err(|_| self.corrupted())?;
+        let decode = |field: &str| STANDARD.decode(field).map_err(|_| self.corrupted());
+        let salt: [u8; SALT_LEN] = decode(&file.salt)?
+            .try_into()
+            .map_err(|_| self.corrupted())?;
+        let nonce = decode(&file.nonce)?;
+        if nonce.len() != 12 {
+            return Err(self.corrupted());
+        }
+
+        let cipher = ChaCha20Poly1305::new(&self.key(cached, salt)?);
+        let plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                decode(&file.ciphertext)?.as_ref(),
+            )
+            .map_err(|_| CredentialStoreError::WrongPassphrase {
+                path: self.path.clone(),
+            })?;
+        let accounts = serde_json::from_slice(&plaintext).map_err(|_| self.corrupted())?;
+        Ok((salt, accounts))
+    }
+
+    /// Encrypts every account with a new nonce. The file is replaced by a complete new one, so a
+    /// failed write leaves the old one in place. On unix it's readable by its owner only
+    fn write(
+        &self,
+        cached: &mut Option<DerivedKey>,
+        salt: [u8; SALT_LEN],
+        accounts: &BTreeMap<String, FilmwebCookies>,
+    ) -> Result<(), CredentialStoreError> {
+        let plaintext = serde_json::to_vec(accounts).expect("cookies can be serialized");
+        let cipher = ChaCha20Poly1305::new(&self.key(cached, salt)?);
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_ref())
+            .expect("plaintext isn't too long");
+        let file = EncryptedFile {
+            salt: STANDARD.encode(salt),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        };
+        let contents = serde_json::to_vec_pretty(&file).expect("file can be serialized");
+
+        write_private_file(&self.path, &contents).map_err(|e| self.io_error(e))
+    }
+}
+
+impl CredentialStore for EncryptedFileStore {
+    fn load(&self, account: &str) -> Result<Option<FilmwebCookies>, CredentialStoreError> {
+        let mut cached = self.key.lock().unwrap();
+        let (_, mut accounts) = self.read(&mut cached)?;
+        Ok(accounts.remove(account))
+    }
+
+    fn store(&self, account: &str, cookies: &FilmwebCookies) -> Result<(), CredentialStoreError> {
+        let mut cached = self.key.lock().unwrap();
+        let (salt, mut accounts) = self.read(&mut cached)?;
+        accounts.insert(account.to_owned(), cookies.clone());
+        self.write(&mut cached, salt, &accounts)
+    }
+}
+
+// The passphrase is a secret, keep it out of logs
+impl std::fmt::Debug for EncryptedFileStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptedFileStore")
+            .field("path", &self.path)
+            .field("passphrase", &"<redacted>")
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypting_credentials() {
+        let dir = std::env::temp_dir().join(format!("filmed-credentials-{}", std::process::id()));
+        let path = dir.join("credentials.bin");
+        let store = EncryptedFileStore::new(&path, "correct horse");
+        assert_eq!(store.load("main").unwrap(), None);
+
+        let cookies = FilmwebCookies::new("token", "session", "secret-jwt");
+        store.store("main", &cookies).unwrap();
+        store
+            .store("other", &FilmwebCookies::new("a", "b", "c"))
+            .unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains("secret-jwt"));
+        assert!(!crate::utils::temp_path(&path).exists());
+
+        // A file written before keeps none of its old permissions
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+            store.store("main", &cookies).unwrap();
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let reopened = EncryptedFileStore::new(&path, "correct horse");
+        assert_eq!(reopened.load("main").unwrap(), Some(cookies));
+        assert!(matches!(
+            EncryptedFileStore::new(&path, "wrong").load("main"),
+            Err(CredentialStoreError::WrongPassphrase { .. })
+        ));
+
+        fs::write(&path, "not json").unwrap();
+        assert!(matches!(
+            reopened.load("main"),
+            Err(CredentialStoreError::Corrupted { .. })
+        ));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
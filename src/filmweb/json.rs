@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 
 // TODO: use serde rename_all
 #[derive(Serialize, Deserialize, Debug)]
+#[allow(dead_code)]
 pub struct SearchResults {
     pub total: u32,
     #[serde(rename = "searchCounts")]
@@ -80,6 +81,12 @@ pub struct FwApiGenre {
     _name: String,
 }
 
+/// Anything Filmweb's api sends just a name of, e.g. a game's platform
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FwApiName {
+    pub name: String,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FwApiTitle {
     pub title: String,
@@ -113,7 +120,15 @@ pub struct Preview {
     #[serde(skip)]
     poster: String,
     pub genres: Vec<FwApiGenre>,
-    pub duration: u16,
+    /// Missing for games
+    #[serde(default)]
+    pub duration: Option<u16>,
+    /// Listed for games only
+    #[serde(default)]
+    pub platforms: Vec<FwApiName>,
+    /// Listed for games only
+    #[serde(default)]
+    pub developers: Vec<FwApiName>,
     #[serde(skip)]
     #[serde(rename = "mainReviewId")]
     main_review_id: u16,
@@ -121,3 +136,48 @@ pub struct Preview {
     #[serde(rename = "mainCast")]
     main_cast: MainCast,
 }
+
+/// Preview of one of the titles asked for in a batch
+#[derive(Deserialize, Debug)]
+pub struct BatchedPreview {
+    pub id: u32,
+    #[serde(flatten)]
+    pub preview: Preview,
+}
+
+/// Entry of a user's list of rated titles. `favorite` is left out when it's false, or when the
+/// list isn't the logged-in user's own
+#[derive(Deserialize, Debug)]
+pub struct VoteEntry {
+    pub entity: u32,
+    pub rate: u8,
+    pub favorite: Option<bool>,
+    #[serde(default, rename = "viewDate")]
+    pub view_date: u32,
+    #[serde(default)]
+    pub timestamp: u128,
+    #[serde(flatten)]
+    pub comment: FilmwebApiComment,
+}
+
+/// Short text attached to a rating, sent along with the rating. Every field is left out when
+/// there's no comment
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct FilmwebApiComment {
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default, rename = "commentTimestamp")]
+    pub timestamp: u64,
+    #[serde(default, rename = "commentLikes")]
+    pub likes: u32,
+}
+
+/// Entry of a user's want-to-see list, with how much the title is wanted and when it was added
+#[derive(Deserialize, Debug)]
+pub struct WantToSeeEntry {
+    pub entity: u32,
+    #[serde(default)]
+    pub level: u8,
+    #[serde(default)]
+    pub timestamp: u128,
+}
@@ -0,0 +1,98 @@
+use crate::error::FilmwebErrors;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde_json::Value;
+
+/// Claims of a Filmweb `JWT`, read from its payload without checking the signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JwtClaims {
+    pub user_id: Option<u64>,
+    pub issued_at: Option<SystemTime>,
+    pub expires_at: Option<SystemTime>,
+}
+
+impl JwtClaims {
+    pub fn decode(jwt: &str) -> Result<Self, FilmwebErrors> {
+        let payload = jwt.split('.').nth(1).ok_or(FilmwebErrors::InvalidJwt)?;
+        let payload = URL_SAFE_NO_PAD
+            .decode(payload.trim_end_matches('='))
+            .map_err(|_| FilmwebErrors::InvalidJwt)?;
+        let claims: Value =
+            serde_json::from_slice(&payload).map_err(|_| FilmwebErrors::InvalidJwt)?;
+        let time = |name| {
+            claims[name]
+                .as_u64()
+                .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+        };
+        let user_id = ["uid", "userId", "sub"].iter().find_map(|name| {
+            let claim = &claims[*name];
+            claim
+                .as_u64()
+                .or_else(|| claim.as_str().and_then(|id| id.parse().ok()))
+        });
+        Ok(Self {
+            user_id,
+            issued_at: time("iat"),
+            expires_at: time("exp"),
+        })
+    }
+
+    /// Time left until the JWT expires, zero once it has. `None` if it doesn't expire
+    #[must_use]
+    pub fn remaining(&self) -> Option<Duration> {
+        self.expires_at.map(|expires_at| {
+            expires_at
+                .duration_since(SystemTime::now())
+                .unwrap_or_default()
+        })
+    }
+
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.remaining() == Some(Duration::ZERO)
+    }
+
+    /// Whether the JWT expires before `duration` passes, e.g. before a scrape finishes
+    #[must_use]
+    pub fn expires_within(&self, duration: Duration) -> bool {
+        self.remaining()
+            .is_some_and(|remaining| remaining < duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jwt(payload: &str) -> String {
+        format!(
+            "eyJhbGciOiJIUzI1NiJ9.{}.signature",
+            URL_SAFE_NO_PAD.encode(payload)
+        )
+    }
+
+    #[test]
+    fn decoding_claims() {
+        let claims =
+            JwtClaims::decode(&jwt(r#"{"uid":1234,"iat":1700000000,"exp":4102444800}"#)).unwrap();
+        assert_eq!(claims.user_id, Some(1234));
+        assert_eq!(
+            claims.issued_at,
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        );
+        assert!(!claims.is_expired());
+        assert!(!claims.expires_within(Duration::from_secs(60 * 60)));
+
+        let claims = JwtClaims::decode(&jwt(r#"{"sub":"42","exp":1700000000}"#)).unwrap();
+        assert_eq!(claims.user_id, Some(42));
+        assert!(claims.is_expired());
+        assert!(claims.expires_within(Duration::from_secs(1)));
+
+        assert!(matches!(
+            JwtClaims::decode("not a jwt"),
+            Err(FilmwebErrors::InvalidJwt)
+        ));
+    }
+}
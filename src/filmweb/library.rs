@@ -0,0 +1,926 @@
+//! Fetching a user's whole library from Filmweb's JSON api: pages of the user's vote lists, and
+//! previews of the listed titles in batches. That's a couple of requests per page of titles,
+//! instead of a few per title when scraping user pages.
+
+#[cfg(feature = "async")]
+use crate::http::AsyncTransport;
+use crate::http::Transport;
+use crate::User;
+
+use super::auth::{
+    self, FilmwebApiDetails, FilmwebRatedTitle, FilmwebUserCounts, FilmwebWantToSeeDetails,
+    RatedPage, UserPage, UserPageType, Vote,
+};
+use super::json::{BatchedPreview, Preview, VoteEntry, WantToSeeEntry};
+use super::{
+    parse_user_page, public, FilmwebErrors, FilmwebTitle, LazyTransport, ScrapedFilmwebTitleData,
+    TitleType,
+};
+
+use serde::de::{DeserializeOwned, IgnoredAny};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Most titles asked for in one request for previews
+pub(super) const PREVIEW_BATCH: usize = 50;
+
+/// One of the lists a library is made of
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum VoteList {
+    Rated(TitleType),
+    WantToSee(TitleType),
+}
+
+impl VoteList {
+    /// Lists holding the titles of `page_type`, the watchlist has one of films and one of serials
+    pub fn of(page_type: UserPageType) -> Vec<Self> {
+        match page_type {
+            UserPageType::RatedFilms => vec![Self::Rated(TitleType::Movie)],
+            UserPageType::RatedShows => vec![Self::Rated(TitleType::Show)],
+            UserPageType::RatedGames => vec![Self::Rated(TitleType::Game)],
+            UserPageType::Watchlist => vec![
+                Self::WantToSee(TitleType::Movie),
+                Self::WantToSee(TitleType::Show),
+            ],
+        }
+    }
+
+    const fn title_type(self) -> TitleType {
+        match self {
+            Self::Rated(title_type) | Self::WantToSee(title_type) => title_type,
+        }
+    }
+
+    const fn kind(self) -> &'static str {
+        match self.title_type() {
+            TitleType::Movie => "film",
+            TitleType::Show => "serial",
+            TitleType::Game => "game",
+        }
+    }
+}
+
+/// Whose lists are read. Only the logged-in user sees their own favorites
+#[derive(Clone, Copy, Debug)]
+pub(super) enum ListOwner<'a> {
+    LoggedIn,
+    Public(&'a str),
+}
+
+impl ListOwner<'_> {
+    /// Favorite flag of a title, absent when false on user's own lists
+    const fn favorite(self, favorite: Option<bool>) -> Option<bool> {
+        match (self, favorite) {
+            (Self::LoggedIn, None) => Some(false),
+            (Self::LoggedIn, favorite) => favorite,
+            (Self::Public(_), _) => None,
+        }
+    }
+
+    /// Error for a list that couldn't be read
+    fn unreadable(self) -> FilmwebErrors {
+        match self {
+            Self::LoggedIn => FilmwebErrors::InvalidJwt,
+            Self::Public(username) => FilmwebErrors::PrivateProfile {
+                username: username.to_owned(),
+            },
+        }
+    }
+
+    /// Url of the owner's vote of a title scraped from `page`
+    fn vote_url(self, base_url: &str, page: UserPage, scraped: &ScrapedFilmwebTitleData) -> String {
+        match self {
+            Self::LoggedIn => auth::vote_details_url(base_url, page, scraped),
+            Self::Public(username) => public::public_vote_url(base_url, username, page, scraped),
+        }
+    }
+
+    fn parse_vote(self, page: UserPage, response: &str) -> Result<Vote, FilmwebErrors> {
+        match self {
+            Self::LoggedIn => auth::parse_vote_details(page, response),
+            Self::Public(username) => public::parse_public_vote(username, page, response),
+        }
+    }
+
+    fn parse_count(self, response: &str) -> Result<u16, FilmwebErrors> {
+        match self {
+            Self::LoggedIn => Ok(response.parse::<u16>()?),
+            Self::Public(username) => public::parse_count(username, response),
+        }
+    }
+}
+
+/// A user whose pages and lists can be read, whether with cookies or without
+pub(super) trait UserApi: User {
+    fn base_url(&self) -> &str;
+
+    fn owner(&self) -> ListOwner<'_>;
+
+    /// Transport of the requests that don't depend on the user, e.g. of previews
+    fn transport(&self) -> &Arc<dyn Transport>;
+
+    /// Fetches a response as the user
+    fn get_text(&self, url: &str) -> Result<String, FilmwebErrors>;
+
+    /// Fetches a response of the api that should be a `T`, e.g. by renewing an expired JWT
+    fn get_renewing<T: DeserializeOwned>(&self, url: &str) -> Result<String, FilmwebErrors> {
+        self.get_text(url)
+    }
+}
+
+/// Async flavour of [`UserApi`]
+#[cfg(feature = "async")]
+pub(super) trait AsyncUserApi: User {
+    fn base_url(&self) -> &str;
+
+    fn owner(&self) -> ListOwner<'_>;
+
+    fn transport(&self) -> &Arc<dyn AsyncTransport>;
+
+    async fn get_text(&self, url: &str) -> Result<String, FilmwebErrors>;
+
+    async fn get_renewing<T: DeserializeOwned>(&self, url: &str) -> Result<String, FilmwebErrors> {
+        self.get_text(url).await
+    }
+}
+
+fn fetch_rated_count(
+    user: &impl UserApi,
+    title_type: &'static str,
+    title_type2: &'static str,
+) -> Result<u16, FilmwebErrors> {
+    let url = auth::rated_count_url(user.base_url(), user.username(), title_type, title_type2);
+    user.owner().parse_count(&user.get_text(&url)?)
+}
+
+/// Fetches how many titles there are in each part of user's library
+pub(super) fn rated_counts(user: &impl UserApi) -> Result<FilmwebUserCounts, FilmwebErrors> {
+    let rated_movies_count = fetch_rated_count(user, "votes", "film")?;
+    let rated_shows_count = fetch_rated_count(user, "votes", "serial")?;
+    let rated_games_count = fetch_rated_count(user, "votes", "game")?;
+    let watchlisted_count = fetch_rated_count(user, "want2see", "film")?
+        + fetch_rated_count(user, "want2see", "serial")?;
+
+    Ok(FilmwebUserCounts {
+        movies: rated_movies_count,
+        shows: rated_shows_count,
+        games: rated_games_count,
+        watchlist: watchlisted_count,
+    })
+}
+
+#[cfg(feature = "async")]
+async fn fetch_rated_count_async(
+    user: &impl AsyncUserApi,
+    title_type: &'static str,
+    title_type2: &'static str,
+) -> Result<u16, FilmwebErrors> {
+    let url = auth::rated_count_url(user.base_url(), user.username(), title_type, title_type2);
+    user.owner().parse_count(&user.get_text(&url).await?)
+}
+
+/// See [`rated_counts`]
+#[cfg(feature = "async")]
+pub(super) async fn rated_counts_async(
+    user: &impl AsyncUserApi,
+) -> Result<FilmwebUserCounts, FilmwebErrors> {
+    let rated_movies_count = fetch_rated_count_async(user, "votes", "film").await?;
+    let rated_shows_count = fetch_rated_count_async(user, "votes", "serial").await?;
+    let rated_games_count = fetch_rated_count_async(user, "votes", "game").await?;
+    let watchlisted_count = fetch_rated_count_async(user, "want2see", "film").await?
+        + fetch_rated_count_async(user, "want2see", "serial").await?;
+
+    Ok(FilmwebUserCounts {
+        movies: rated_movies_count,
+        shows: rated_shows_count,
+        games: rated_games_count,
+        watchlist: watchlisted_count,
+    })
+}
+
+/// Every title of one part of user's library, `expected` of them according to user's counts
+pub(super) fn rated_titles(
+    user: &impl UserApi,
+    page_type: UserPageType,
+    expected: u16,
+) -> RatedTitles<'_> {
+    RatedTitles::new(page_type, expected, move |list, page| {
+        fetch_library_page(user, list, page)
+    })
+}
+
+/// Titles of a page of one of user's lists, and whether the list ended before that page. A page
+/// whose titles all lack previews has no titles, although the list goes on
+pub(super) type LibraryPage = (bool, Vec<FilmwebRatedTitle>);
+
+/// Fetches a page of one of user's lists, then previews of its titles in batches
+fn fetch_library_page(
+    user: &impl UserApi,
+    list: VoteList,
+    page: u16,
+) -> Result<LibraryPage, FilmwebErrors> {
+    let (base_url, owner) = (user.base_url(), user.owner());
+    let url = vote_list_url(base_url, owner, list, page);
+    let votes = parse_vote_list(owner, list, &user.get_renewing::<Vec<IgnoredAny>>(&url)?)?;
+
+    let mut titles = Vec::with_capacity(votes.len());
+    for batch in votes.chunks(PREVIEW_BATCH) {
+        let url = previews_url(base_url, batch);
+        let response = user.transport().get(&url)?.error_for_status(&url)?;
+        let previews = parse_previews(&response.text())?;
+        titles.extend(assemble(
+            base_url,
+            list,
+            batch,
+            previews,
+            Some(LazyTransport::Blocking(Arc::clone(user.transport()))),
+        ));
+    }
+    Ok((votes.is_empty(), titles))
+}
+
+/// Fetches lists page by page, like [`RatedTitles`] does
+#[cfg(feature = "async")]
+pub(super) async fn fetch_library_async(
+    user: &impl AsyncUserApi,
+    page_type: UserPageType,
+    expected: u16,
+) -> Result<Vec<FilmwebRatedTitle>, FilmwebErrors> {
+    let mut titles = Vec::new();
+    for list in VoteList::of(page_type) {
+        for page in 1..=u16::MAX {
+            let (ended, fetched) = fetch_library_page_async(user, list, page).await?;
+            if ended {
+                break;
+            }
+            titles.extend(fetched);
+        }
+    }
+    warn_if_short(titles.len(), expected);
+    Ok(titles)
+}
+
+/// See [`fetch_library_page`]
+#[cfg(feature = "async")]
+async fn fetch_library_page_async(
+    user: &impl AsyncUserApi,
+    list: VoteList,
+    page: u16,
+) -> Result<LibraryPage, FilmwebErrors> {
+    let (base_url, owner) = (user.base_url(), user.owner());
+    let url = vote_list_url(base_url, owner, list, page);
+    let response = user.get_renewing::<Vec<IgnoredAny>>(&url).await?;
+    let votes = parse_vote_list(owner, list, &response)?;
+
+    let mut titles = Vec::with_capacity(votes.len());
+    for batch in votes.chunks(PREVIEW_BATCH) {
+        let url = previews_url(base_url, batch);
+        let response = user.transport().get(&url).await?;
+        let previews = parse_previews(&response.error_for_status(&url)?.text())?;
+        let lazy = LazyTransport::Async(Arc::clone(user.transport()));
+        titles.extend(assemble(base_url, list, batch, previews, Some(lazy)));
+    }
+    Ok((votes.is_empty(), titles))
+}
+
+/// Scrapes a user page together with user's vote of every title on it, leaving alternate titles
+/// and duration to be fetched the first time they're needed
+pub(super) fn scrape(user: &impl UserApi, page: UserPage) -> Result<RatedPage, FilmwebErrors> {
+    let (base_url, owner) = (user.base_url(), user.owner());
+    let url = page.user_url(base_url, user.username());
+    let res = user.get_text(&url)?;
+
+    // Pages past the end of the library have no titles
+    if !res.contains("preview__link") {
+        log::debug!("No titles on {url}");
+        return Ok(RatedPage {
+            rated_titles: Vec::new(),
+        });
+    }
+
+    let mut rated_titles: Vec<_> = Vec::new();
+    for scraped in parse_user_page(&res, base_url, page)? {
+        let url = owner.vote_url(base_url, page, &scraped);
+        let response = match page {
+            UserPage::Watchlist(_) => user.get_renewing::<FilmwebWantToSeeDetails>(&url)?,
+            _ => user.get_renewing::<FilmwebApiDetails>(&url)?,
+        };
+        let vote = owner.parse_vote(page, &response)?;
+        let lazy = LazyTransport::Blocking(Arc::clone(user.transport()));
+        rated_titles.push(FilmwebRatedTitle::from_scraped(page, scraped, lazy, vote));
+    }
+
+    Ok(RatedPage { rated_titles })
+}
+
+/// See [`scrape`], alternate titles and duration are left to the async accessors, e.g.
+/// [`AlternateTitles::alter_titles_async`](crate::AlternateTitles::alter_titles_async)
+#[cfg(feature = "async")]
+pub(super) async fn scrape_async(
+    user: &impl AsyncUserApi,
+    page: UserPage,
+) -> Result<RatedPage, FilmwebErrors> {
+    let (base_url, owner) = (user.base_url(), user.owner());
+    let url = page.user_url(base_url, user.username());
+    let res = user.get_text(&url).await?;
+
+    // Pages past the end of the library have no titles
+    if !res.contains("preview__link") {
+        log::debug!("No titles on {url}");
+        return Ok(RatedPage {
+            rated_titles: Vec::new(),
+        });
+    }
+
+    let mut rated_titles: Vec<_> = Vec::new();
+    for scraped in parse_user_page(&res, base_url, page)? {
+        let url = owner.vote_url(base_url, page, &scraped);
+        let response = match page {
+            UserPage::Watchlist(_) => user.get_renewing::<FilmwebWantToSeeDetails>(&url).await?,
+            _ => user.get_renewing::<FilmwebApiDetails>(&url).await?,
+        };
+        let vote = owner.parse_vote(page, &response)?;
+        let lazy = LazyTransport::Async(Arc::clone(user.transport()));
+        rated_titles.push(FilmwebRatedTitle::from_scraped(page, scraped, lazy, vote));
+    }
+
+    Ok(RatedPage { rated_titles })
+}
+
+pub(super) fn vote_list_url(base_url: &str, owner: ListOwner, list: VoteList, page: u16) -> String {
+    let kind = list.kind();
+    match (owner, list) {
+        (ListOwner::LoggedIn, VoteList::Rated(_)) => {
+            format!("{base_url}/api/v1/logged/vote/{kind}?page={page}")
+        }
+        (ListOwner::LoggedIn, VoteList::WantToSee(_)) => {
+            format!("{base_url}/api/v1/logged/want2see/{kind}?page={page}")
+        }
+        (ListOwner::Public(username), VoteList::Rated(_)) => {
+            format!("{base_url}/api/v1/user/{username}/votes/{kind}?page={page}")
+        }
+        (ListOwner::Public(username), VoteList::WantToSee(_)) => {
+            format!("{base_url}/api/v1/user/{username}/want2see/{kind}?page={page}")
+        }
+    }
+}
+
+/// Url of previews of up to [`PREVIEW_BATCH`] titles
+pub(super) fn previews_url(base_url: &str, votes: &[(u32, Vote)]) -> String {
+    let ids: Vec<String> = votes.iter().map(|(id, _)| id.to_string()).collect();
+    format!("{base_url}/api/v1/films/previews?ids={}", ids.join(","))
+}
+
+/// Returns ids of the titles on a page of a list with their votes, none past its last page
+pub(super) fn parse_vote_list(
+    owner: ListOwner,
+    list: VoteList,
+    response: &str,
+) -> Result<Vec<(u32, Vote)>, FilmwebErrors> {
+    let unreadable = |e| {
+        log::info!("Bad Filmweb's api response: {response}\n{e}");
+        owner.unreadable()
+    };
+    match list {
+        VoteList::Rated(_) => {
+            let entries: Vec<VoteEntry> = serde_json::from_str(response).map_err(unreadable)?;
+            Ok(entries
+                .into_iter()
+                .map(|entry| {
+                    let favorite = owner.favorite(entry.favorite);
+                    let vote = Vote::rated(entry.rate, favorite, entry.timestamp, entry.view_date)
+                        .with_comment(entry.comment);
+                    (entry.entity, vote)
+                })
+                .collect())
+        }
+        VoteList::WantToSee(_) => {
+            let entries: Vec<WantToSeeEntry> =
+                serde_json::from_str(response).map_err(unreadable)?;
+            Ok(entries
+                .into_iter()
+                .map(|entry| {
+                    let favorite = owner.favorite(None);
+                    let vote = Vote::watchlisted(favorite, entry.level, entry.timestamp);
+                    (entry.entity, vote)
+                })
+                .collect())
+        }
+    }
+}
+
+pub(super) fn parse_previews(response: &str) -> Result<HashMap<u32, Preview>, FilmwebErrors> {
+    let previews: Vec<BatchedPreview> = serde_json::from_str(response)?;
+    Ok(previews
+        .into_iter()
+        .map(|batched| (batched.id, batched.preview))
+        .collect())
+}
+
+/// Puts listed titles together with their previews, skipping the ones Filmweb sent no preview of.
+/// Alternate titles beyond the listed ones are fetched with `lazy`, if there's one
+pub(super) fn assemble(
+    base_url: &str,
+    list: VoteList,
+    votes: &[(u32, Vote)],
+    mut previews: HashMap<u32, Preview>,
+    lazy: Option<LazyTransport>,
+) -> Vec<FilmwebRatedTitle> {
+    votes
+        .iter()
+        .filter_map(|(id, vote)| {
+            let id = *id;
+            let Some(preview) = previews.remove(&id) else {
+                log::warn!("No preview of title {id}, skipping it");
+                return None;
+            };
+            let title = FilmwebTitle::from_listed_preview(
+                base_url,
+                id,
+                list.kind(),
+                list.title_type(),
+                preview,
+                lazy.clone(),
+            );
+            Some(FilmwebRatedTitle::new(title, vote.clone()))
+        })
+        .collect()
+}
+
+pub(super) fn warn_if_short(found: usize, expected: u16) {
+    if found < usize::from(expected) {
+        log::warn!("Library ended after {found} titles, {expected} were expected");
+    }
+}
+
+/// Every title of one part of a user's library, e.g. from
+/// [`FilmwebUser::rated_films`](super::FilmwebUser::rated_films). Pages of the user's lists are
+/// fetched as the iterator advances, each with previews of its titles, until the lists come back
+/// empty. The user's counts may be outdated, e.g. those of a restored session, so they don't
+/// limit the paging: they're only for reporting progress, and a library ending short of its count
+/// is logged. An error ends the iteration. Listed titles come with just their main and original
+/// titles, the rest of [`AlternateTitles::alter_titles`](crate::AlternateTitles::alter_titles) is
+/// fetched once those are used up, e.g. by an IMDb lookup that didn't match them. Titles listed by
+/// the async flavours fetch it with
+/// [`AlternateTitles::alter_titles_async`](crate::AlternateTitles::alter_titles_async) instead
+///
+/// # Examples
+/// ```rust,no_run
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use filmed::filmweb::FilmwebUser;
+/// use filmed::{RatedTitle, Title};
+///
+/// let user = FilmwebUser::new("token", "session", "jwt")?;
+/// for title in user.rated_films() {
+///     let title = title?;
+///     println!("{}: {:?}", title.title(), title.rating());
+/// }
+/// #     Ok(())
+/// # }
+/// ```
+pub struct RatedTitles<'a> {
+    fetch: Box<dyn Fn(VoteList, u16) -> Result<LibraryPage, FilmwebErrors> + 'a>,
+    /// Lists after the current one
+    lists: std::vec::IntoIter<VoteList>,
+    /// `None` once the library is exhausted
+    next_page: Option<(VoteList, u16)>,
+    titles: std::vec::IntoIter<FilmwebRatedTitle>,
+    expected: u16,
+    yielded: usize,
+}
+
+impl<'a> RatedTitles<'a> {
+    pub(super) fn new<F>(page_type: UserPageType, expected: u16, fetch: F) -> Self
+    where
+        F: Fn(VoteList, u16) -> Result<LibraryPage, FilmwebErrors> + 'a,
+    {
+        let mut lists = VoteList::of(page_type).into_iter();
+        let next_page = lists.next().map(|list| (list, 1));
+        Self {
+            fetch: Box::new(fetch),
+            lists,
+            next_page,
+            titles: Vec::new().into_iter(),
+            expected,
+            yielded: 0,
+        }
+    }
+
+    /// Number of titles the user's counts report, e.g. to show progress. The library may turn
+    /// out longer or shorter, if they're outdated
+    #[must_use]
+    pub const fn expected(&self) -> u16 {
+        self.expected
+    }
+
+    /// Number of titles yielded so far
+    #[must_use]
+    pub const fn yielded(&self) -> usize {
+        self.yielded
+    }
+
+    fn next_list(&mut self) -> Option<(VoteList, u16)> {
+        self.lists.next().map(|list| (list, 1))
+    }
+}
+
+impl Iterator for RatedTitles<'_> {
+    type Item = Result<FilmwebRatedTitle, FilmwebErrors>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(title) = self.titles.next() {
+                self.yielded += 1;
+                return Some(Ok(title));
+            }
+            let (list, page) = self.next_page.take()?;
+            match (self.fetch)(list, page) {
+                Ok((true, _)) => {
+                    self.next_page = self.next_list();
+                    if self.next_page.is_none() {
+                        warn_if_short(self.yielded, self.expected);
+                    }
+                }
+                Ok((false, titles)) => {
+                    self.titles = titles.into_iter();
+                    self.next_page = match page.checked_add(1) {
+                        Some(next) => Some((list, next)),
+                        None => self.next_list(),
+                    };
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.titles.len(), None)
+    }
+}
+
+impl std::iter::FusedIterator for RatedTitles<'_> {}
+
+/// Orders of a watchlist, e.g. to pick what to watch next with
+/// [`FilmwebUser::sorted_watchlist`](super::FilmwebUser::sorted_watchlist). Titles missing what
+/// they're ordered by come last
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WatchlistOrder {
+    /// Most wanted first, among equally wanted ones the one waiting the longest
+    MostWanted,
+    /// Most recently added first
+    Newest,
+    /// Added the longest ago first
+    Oldest,
+}
+
+impl WatchlistOrder {
+    /// Sorts titles, keeping the order of equal ones
+    pub fn sort(self, titles: &mut [FilmwebRatedTitle]) {
+        let oldest_first = |title: &FilmwebRatedTitle| {
+            let added = title.watchlisted_at();
+            (added.is_none(), added)
+        };
+        match self {
+            Self::MostWanted => {
+                titles.sort_by_key(|title| (Reverse(title.interest_level()), oldest_first(title)));
+            }
+            Self::Newest => titles.sort_by_key(|title| Reverse(title.watchlisted_at())),
+            Self::Oldest => titles.sort_by_key(oldest_first),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filmweb::{FilmwebUser, PublicFilmwebUser};
+    use crate::http::testing::FakeTransport;
+    use crate::http::TransportError;
+    use crate::imdb::IMDb;
+    use crate::{AlternateTitles, IMDbLookup, RatedTitle, Title};
+
+    use std::sync::Arc;
+
+    fn previews(ids: &[u32]) -> String {
+        let previews: Vec<String> = ids
+            .iter()
+            .map(|id| {
+                format!(
+                    r#"{{"id":{id},"year":2021,"title":{{"title":"Tytuł {id}","country":"PL","lang":"pl"}},"originalTitle":{{"title":"Title {id}","country":"US","lang":"en","original":true}},"genres":[{{"id":6}}],"duration":90}}"#
+                )
+            })
+            .collect();
+        format!("[{}]", previews.join(","))
+    }
+
+    const GAME_PREVIEW: &str = r#"[{"id":5,"year":2015,"title":{"title":"Wiedźmin 3","country":"PL","lang":"pl"},"genres":[{"id":1}],"platforms":[{"name":"PC"},{"name":"PS4"}],"developers":[{"name":"CD Projekt RED"}]}]"#;
+
+    #[test]
+    fn fetching_library_in_bulk() {
+        let settings = r#"<div class="mainSettings__groupItemStateContent">a</div>
+            <div class="mainSettings__groupItemStateContent">b</div>
+            <div class="mainSettings__groupItemStateContent">tester</div>"#;
+        let films1 = r#"[{"entity":1,"rate":7,"favorite":true,"viewDate":20230115,"timestamp":1673788800000},{"entity":2,"rate":5}]"#;
+        let previews1 = previews(&[1, 2]);
+        let previews3 = previews(&[3]);
+        let previews4 = previews(&[4]);
+        let transport = Arc::new(FakeTransport::new(vec![
+            ("/settings", settings),
+            // One film was rated since the counts were fetched
+            ("/votes/film/count", "2"),
+            ("/votes/serial/count", "2"),
+            ("/votes/game/count", "1"),
+            ("/want2see/film/count", "1"),
+            ("/want2see/serial/count", "0"),
+            ("/logged/vote/film?page=1", films1),
+            ("/logged/vote/film?page=2", r#"[{"entity":3,"rate":9}]"#),
+            ("/logged/vote/serial?page=1", r#"[{"entity":3,"rate":6}]"#),
+            (
+                "/logged/want2see/film?page=1",
+                r#"[{"entity":4,"level":5,"timestamp":1673788800000}]"#,
+            ),
+            ("/previews?ids=1,2", &previews1),
+            ("/previews?ids=3", &previews3),
+            ("/previews?ids=4", &previews4),
+            ("/logged/vote/game?page=1", r#"[{"entity":5,"rate":8}]"#),
+            ("/previews?ids=5", GAME_PREVIEW),
+            ("?page=", "[]"),
+        ]));
+        let user = FilmwebUser::builder()
+            .base_url("http://filmweb.test")
+            .transport(Arc::clone(&transport))
+            .without_rate_limit()
+            .build("token", "session", "jwt")
+            .unwrap();
+        let sent = || transport.requests.lock().unwrap().len();
+        let before = sent();
+
+        let films: Vec<_> = user.rated_films().map(Result::unwrap).collect();
+        assert_eq!(
+            films.iter().map(|t| t.title().as_str()).collect::<Vec<_>>(),
+            vec!["Tytuł 1", "Tytuł 2", "Tytuł 3"]
+        );
+        assert_eq!(films[0].rating(), Some(7));
+        assert_eq!(films[0].favorited(), Some(true));
+        assert_eq!(films[1].favorited(), Some(false));
+        assert_eq!(films[0].duration(), Some(90));
+        assert_eq!(films[0].url(), "http://filmweb.test/film/Tytuł 1-2021-1");
+        // Two pages of votes with a batch of previews each, then an empty page
+        assert_eq!(sent() - before, 5);
+        assert_eq!(user.rated_films().expected(), 2);
+
+        // Only one serial, although two were counted
+        let shows: Vec<_> = user.rated_shows().map(Result::unwrap).collect();
+        assert_eq!(shows.len(), 1);
+        assert_eq!(shows[0].title_type(), &TitleType::Show);
+
+        let watchlist: Vec<_> = user.watchlist().map(Result::unwrap).collect();
+        assert_eq!(watchlist.len(), 1);
+        assert!(watchlist[0].is_watchlisted() && watchlist[0].rating().is_none());
+        assert_eq!(watchlist[0].interest_level(), Some(5));
+        assert!(watchlist[0].watchlisted_at().is_some());
+
+        let games: Vec<_> = user.rated_games().map(Result::unwrap).collect();
+        assert_eq!(games[0].title_type(), &TitleType::Game);
+        assert_eq!(games[0].url(), "http://filmweb.test/game/Wiedźmin 3-2015-5");
+        assert_eq!((games[0].rating(), games[0].duration()), (Some(8), None));
+        assert!(games[0].genres().is_empty());
+        let game = games[0].game().unwrap();
+        assert_eq!(game.platforms, vec!["PC", "PS4"]);
+        assert_eq!(game.developer.as_deref(), Some("CD Projekt RED"));
+        assert!(films[0].game().is_none());
+    }
+
+    #[test]
+    fn paging_past_pages_without_previews() {
+        let previews3 = previews(&[3]);
+        let routes = || {
+            vec![
+                ("/count", "3"),
+                (
+                    "/user/tester/votes/film?page=1",
+                    r#"[{"entity":1,"rate":7},{"entity":2,"rate":5}]"#,
+                ),
+                (
+                    "/user/tester/votes/film?page=2",
+                    r#"[{"entity":3,"rate":9}]"#,
+                ),
+                // Both titles of the first page were removed
+                ("/previews?ids=1,2", "[]"),
+                ("/previews?ids=3", previews3.as_str()),
+                ("?page=", "[]"),
+            ]
+        };
+        let builder = PublicFilmwebUser::builder()
+            .base_url("http://filmweb.test")
+            .without_rate_limit();
+        let titles = |films: Vec<FilmwebRatedTitle>| -> Vec<String> {
+            films.iter().map(|t| t.title().clone()).collect()
+        };
+
+        let user = builder
+            .clone()
+            .transport(FakeTransport::new(routes()))
+            .build_public("tester")
+            .unwrap();
+        let films = user.rated_films().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(titles(films), ["Tytuł 3"]);
+
+        #[cfg(feature = "async")]
+        {
+            use crate::http::testing::Immediate;
+
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            let films = runtime.block_on(async {
+                let user = builder
+                    .async_transport(Immediate(FakeTransport::new(routes())))
+                    .build_public_async("tester")
+                    .await
+                    .unwrap();
+                user.rated_films().await.unwrap()
+            });
+            assert_eq!(titles(films), ["Tytuł 3"]);
+        }
+    }
+
+    #[test]
+    fn failing_on_error_pages_of_previews() {
+        let transport = FakeTransport::new(vec![
+            ("/count", "1"),
+            (
+                "/user/tester/votes/film?page=1",
+                r#"[{"entity":1,"rate":7}]"#,
+            ),
+        ])
+        .with_status("/previews", 503, "<html>Przerwa techniczna</html>");
+        let user = PublicFilmwebUser::builder()
+            .base_url("http://filmweb.test")
+            .transport(transport)
+            .without_retries()
+            .without_rate_limit()
+            .build_public("tester")
+            .unwrap();
+        assert!(matches!(
+            user.rated_films().next(),
+            Some(Err(FilmwebErrors::TransportError {
+                source: TransportError::Status { status: 503, .. }
+            }))
+        ));
+    }
+
+    #[test]
+    fn matching_listed_titles_by_titles_that_werent_listed() {
+        let titles_page = r#"<div class="filmTitlesSection__title">Tytuł 1</div>
+            <div class="filmTitlesSection__desc">główny</div>
+            <div class="filmTitlesSection__title">Title 1</div>
+            <div class="filmTitlesSection__desc">oryginalny</div>
+            <div class="filmTitlesSection__title">Saying 1</div>
+            <div class="filmTitlesSection__desc">USA</div>"#;
+        let previews1 = previews(&[1]);
+        let filmweb = Arc::new(FakeTransport::new(vec![
+            ("/count", "1"),
+            (
+                "/user/tester/votes/film?page=1",
+                r#"[{"entity":1,"rate":7}]"#,
+            ),
+            ("/previews?ids=1", &previews1),
+            ("/film/Tytuł 1-2021-1/titles", titles_page),
+            ("?page=", "[]"),
+        ]));
+        let user = PublicFilmwebUser::builder()
+            .base_url("http://filmweb.test")
+            .transport(Arc::clone(&filmweb))
+            .without_rate_limit()
+            .build_public("tester")
+            .unwrap();
+        // IMDb knows the film by its American title only
+        let imdb = IMDb::builder()
+            .base_url("http://imdb.test")
+            .transport(FakeTransport::new(vec![
+                (
+                    "/find?q=Saying 1 2021",
+                    r#"<a class="ipc-metadata-list-summary-item__t" href="/title/tt0000001/">Saying 1</a>
+                    <ul><li class="ipc-metadata-list-summary-item__li">2021</li></ul>"#,
+                ),
+                (
+                    "/title/tt0000001/",
+                    r#"<html><head><title>Saying 1 (2021) - IMDb</title></head><body><ul>
+                    <li class="ipc-inline-list__item">Cast &amp; crew</li>
+                    <li class="ipc-inline-list__item">User reviews</li>
+                    <li class="ipc-inline-list__item">Trivia</li>
+                    <li class="ipc-inline-list__item">FAQ</li>
+                    <li class="ipc-inline-list__item">1h 30m</li></ul>
+                    <span class="ipc-chip__text">Drama</span></body></html>"#,
+                ),
+            ]))
+            .without_rate_limit()
+            .build()
+            .unwrap();
+        let fetched_titles = || {
+            filmweb
+                .requests
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|request| request.url.ends_with("/titles"))
+                .count()
+        };
+
+        let mut films: Vec<_> = user.rated_films().map(Result::unwrap).collect();
+        assert_eq!(fetched_titles(), 0);
+        assert_eq!(films[0].alter_titles().unwrap().len(), 2);
+        films[0].set_imdb_data_with_lookup(&imdb).unwrap();
+        assert_eq!(films[0].imdb_data().unwrap().title(), "Saying 1");
+        assert_eq!(fetched_titles(), 1);
+    }
+
+    #[test]
+    fn ordering_watchlists() {
+        let title = |id, level, timestamp| {
+            let preview = previews(&[id]);
+            let previews = parse_previews(&preview).unwrap();
+            let list = VoteList::WantToSee(TitleType::Movie);
+            let votes = [(id, Vote::watchlisted(None, level, timestamp))];
+            assert_eq!(votes[0].1.interest, (level > 0).then_some(level));
+            assemble("http://filmweb.test", list, &votes, previews, None).remove(0)
+        };
+        let mut titles = vec![
+            title(1, 3, 2_000),
+            title(2, 5, 3_000),
+            title(3, 0, 0),
+            title(4, 5, 1_000),
+        ];
+        let ids = |titles: &[FilmwebRatedTitle]| -> Vec<String> {
+            titles.iter().map(|t| t.title().clone()).collect()
+        };
+
+        WatchlistOrder::MostWanted.sort(&mut titles);
+        assert_eq!(ids(&titles), ["Tytuł 4", "Tytuł 2", "Tytuł 1", "Tytuł 3"]);
+        WatchlistOrder::Newest.sort(&mut titles);
+        assert_eq!(ids(&titles), ["Tytuł 2", "Tytuł 1", "Tytuł 4", "Tytuł 3"]);
+        WatchlistOrder::Oldest.sort(&mut titles);
+        assert_eq!(ids(&titles), ["Tytuł 4", "Tytuł 1", "Tytuł 2", "Tytuł 3"]);
+    }
+
+    #[test]
+    fn exporting_reviews_to_letterboxd() {
+        let votes = parse_vote_list(
+            ListOwner::LoggedIn,
+            VoteList::Rated(TitleType::Movie),
+            r#"[{"entity":1,"rate":8,"viewDate":20230115,"comment":"Dobry, \"ale\" za długi","commentLikes":2},{"entity":2,"rate":4}]"#,
+        )
+        .unwrap();
+        let mut titles = assemble(
+            "http://filmweb.test",
+            VoteList::Rated(TitleType::Movie),
+            &votes,
+            parse_previews(&previews(&[1, 2])).unwrap(),
+            None,
+        );
+        assert_eq!(titles[0].comment().unwrap().likes, 2);
+        assert_eq!(titles[1].comment(), None);
+        titles.extend(assemble(
+            "http://filmweb.test",
+            VoteList::WantToSee(TitleType::Movie),
+            &[(3, Vote::watchlisted(None, 1, 0))],
+            parse_previews(&previews(&[3])).unwrap(),
+            None,
+        ));
+
+        let mut wtr = crate::filmweb::letterboxd_writer(Vec::new()).unwrap();
+        for title in &titles {
+            title.to_csv_letterboxd(&mut wtr).unwrap();
+        }
+        let csv = String::from_utf8(wtr.into_inner().unwrap()).unwrap();
+        assert_eq!(
+            csv,
+            "imdbID,Title,Year,Rating10,WatchedDate,Review\n\
+             ,Tytuł 1,2021,8,2023-01-15,\"Dobry, \"\"ale\"\" za długi\"\n\
+             ,Tytuł 2,2021,4,,\n"
+        );
+    }
+
+    #[test]
+    fn reading_vote_lists() {
+        let list = VoteList::Rated(TitleType::Movie);
+        let votes = parse_vote_list(
+            ListOwner::Public("tester"),
+            list,
+            r#"[{"entity":1,"rate":8}]"#,
+        )
+        .unwrap();
+        assert_eq!(votes[0].0, 1);
+        assert_eq!(votes[0].1.favorited, None);
+        assert!(matches!(
+            parse_vote_list(ListOwner::LoggedIn, list, "<html></html>"),
+            Err(FilmwebErrors::InvalidJwt)
+        ));
+        assert_eq!(
+            vote_list_url("http://filmweb.test", ListOwner::Public("tester"), list, 2),
+            "http://filmweb.test/api/v1/user/tester/votes/film?page=2"
+        );
+    }
+}
@@ -0,0 +1,562 @@
+/// Module containing things related to users browsed without logging in.
+#[cfg(feature = "async")]
+use crate::http::AsyncTransport;
+use crate::http::{Metrics, MetricsSnapshot, Transport};
+use crate::User;
+
+use super::auth::{
+    watchlisted_kind, FilmwebRatedTitle, FilmwebUserCounts, FilmwebWantToSeeDetails, RatedPage,
+    UserPage, UserPageType, Vote,
+};
+use super::json::FilmwebApiComment;
+#[cfg(feature = "async")]
+use super::library::AsyncUserApi;
+use super::library::{self, ListOwner, RatedTitles, UserApi, WatchlistOrder};
+use super::{builder::FilmwebUserBuilder, FilmwebErrors, ScrapedFilmwebTitleData};
+
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Filmweb user with a public profile, browsed without any cookies. Public profiles don't show
+/// which titles are favorited, so [`FilmwebRatedTitle::favorited`] is always `None`
+#[derive(Debug)]
+pub struct PublicFilmwebUser {
+    transport: Arc<dyn Transport>,
+    base_url: String,
+    metrics: Metrics,
+    username: String,
+    counts: FilmwebUserCounts,
+}
+
+/// User's rating of a title, as shown to everyone
+#[derive(Deserialize)]
+struct PublicVote {
+    rate: u8,
+    #[serde(default, rename = "viewDate")]
+    view_date: u32,
+    #[serde(default)]
+    timestamp: u128,
+    #[serde(flatten)]
+    comment: FilmwebApiComment,
+}
+
+pub(super) fn public_vote_url(
+    base_url: &str,
+    username: &str,
+    page: UserPage,
+    scraped: &ScrapedFilmwebTitleData,
+) -> String {
+    let id = scraped.id;
+    match page {
+        UserPage::RatedFilms(_) => {
+            format!("{base_url}/api/v1/user/{username}/vote/film/{id}/details")
+        }
+        UserPage::RatedShows(_) => {
+            format!("{base_url}/api/v1/user/{username}/vote/serial/{id}/details")
+        }
+        UserPage::RatedGames(_) => {
+            format!("{base_url}/api/v1/user/{username}/vote/game/{id}/details")
+        }
+        UserPage::Watchlist(_) => {
+            let kind = watchlisted_kind(scraped);
+            format!("{base_url}/api/v1/user/{username}/want2see/{kind}/{id}/details")
+        }
+    }
+}
+
+fn private_profile(username: &str) -> FilmwebErrors {
+    FilmwebErrors::PrivateProfile {
+        username: username.to_owned(),
+    }
+}
+
+/// Returns the vote from a public vote response, or from want-to-see details in case of a
+/// watchlist. The favorite flag is never known
+pub(super) fn parse_public_vote(
+    username: &str,
+    page: UserPage,
+    response: &str,
+) -> Result<Vote, FilmwebErrors> {
+    let vote = match page {
+        UserPage::Watchlist(_) => serde_json::from_str::<FilmwebWantToSeeDetails>(response)
+            .map(|details| Vote::watchlisted(None, details.level, details.timestamp)),
+        UserPage::RatedFilms(_) | UserPage::RatedShows(_) | UserPage::RatedGames(_) => {
+            serde_json::from_str::<PublicVote>(response).map(|vote| {
+                Vote::rated(vote.rate, None, vote.timestamp, vote.view_date)
+                    .with_comment(vote.comment)
+            })
+        }
+    };
+    vote.map_err(|_| private_profile(username))
+}
+
+pub(super) fn parse_count(username: &str, response: &str) -> Result<u16, FilmwebErrors> {
+    response
+        .trim()
+        .parse::<u16>()
+        .map_err(|_| private_profile(username))
+}
+
+impl PublicFilmwebUser {
+    /// Browses a public profile, failing with [`FilmwebErrors::PrivateProfile`] when it isn't
+    /// one
+    pub fn new<T: Into<String>>(username: T) -> Result<Self, FilmwebErrors> {
+        Self::builder().build_public(username)
+    }
+
+    /// Returns a builder to configure a user, e.g. to query a mock server or a proxy
+    #[must_use]
+    pub fn builder() -> FilmwebUserBuilder {
+        FilmwebUserBuilder::new()
+    }
+
+    pub(super) fn with_transport(
+        transport: Arc<dyn Transport>,
+        metrics: Metrics,
+        username: String,
+        base_url: String,
+    ) -> Result<Self, FilmwebErrors> {
+        let mut user = Self {
+            transport,
+            metrics,
+            base_url,
+            username,
+            counts: FilmwebUserCounts::default(),
+        };
+        user.counts = library::rated_counts(&user)?;
+        Ok(user)
+    }
+
+    /// Origin that all requests are sent to
+    #[must_use]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Requests sent so far, per endpoint
+    #[must_use]
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// Every rated film, fetched page by page, see [`RatedTitles`]
+    pub fn rated_films(&self) -> RatedTitles<'_> {
+        self.library(UserPageType::RatedFilms)
+    }
+
+    /// Every rated serial, fetched page by page, see [`RatedTitles`]
+    pub fn rated_shows(&self) -> RatedTitles<'_> {
+        self.library(UserPageType::RatedShows)
+    }
+
+    /// Every rated game, fetched page by page, see [`RatedTitles`]
+    pub fn rated_games(&self) -> RatedTitles<'_> {
+        self.library(UserPageType::RatedGames)
+    }
+
+    /// Every title the user wants to see, fetched page by page, see [`RatedTitles`]
+    pub fn watchlist(&self) -> RatedTitles<'_> {
+        self.library(UserPageType::Watchlist)
+    }
+
+    /// Every title the user wants to see, in `order`
+    pub fn sorted_watchlist(
+        &self,
+        order: WatchlistOrder,
+    ) -> Result<Vec<FilmwebRatedTitle>, FilmwebErrors> {
+        let mut titles = self.watchlist().collect::<Result<Vec<_>, _>>()?;
+        order.sort(&mut titles);
+        Ok(titles)
+    }
+
+    fn library(&self, page_type: UserPageType) -> RatedTitles<'_> {
+        library::rated_titles(self, page_type, self.counts.of(page_type))
+    }
+
+    /// Scrapes a user page, see [`FilmwebUser::scrape`](super::FilmwebUser::scrape)
+    pub fn scrape(&self, page: UserPage) -> Result<RatedPage, FilmwebErrors> {
+        library::scrape(self, page)
+    }
+}
+
+impl UserApi for PublicFilmwebUser {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn owner(&self) -> ListOwner<'_> {
+        ListOwner::Public(&self.username)
+    }
+
+    fn transport(&self) -> &Arc<dyn Transport> {
+        &self.transport
+    }
+
+    fn get_text(&self, url: &str) -> Result<String, FilmwebErrors> {
+        Ok(self.transport.get(url)?.text())
+    }
+}
+
+impl User for PublicFilmwebUser {
+    fn username(&self) -> &String {
+        &self.username
+    }
+
+    fn num_of_rated_movies(&self) -> u16 {
+        self.counts.movies
+    }
+
+    fn num_of_rated_shows(&self) -> u16 {
+        self.counts.shows
+    }
+
+    fn num_of_rated_games(&self) -> u16 {
+        self.counts.games
+    }
+
+    fn num_of_watchlisted_titles(&self) -> u16 {
+        self.counts.watchlist
+    }
+}
+
+/// Async flavour of [`PublicFilmwebUser`], sharing its parsing code
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct AsyncPublicFilmwebUser {
+    transport: Arc<dyn AsyncTransport>,
+    base_url: String,
+    metrics: Metrics,
+    username: String,
+    counts: FilmwebUserCounts,
+}
+
+#[cfg(feature = "async")]
+impl AsyncPublicFilmwebUser {
+    /// See [`PublicFilmwebUser::new`]
+    pub async fn new<T: Into<String>>(username: T) -> Result<Self, FilmwebErrors> {
+        FilmwebUserBuilder::new().build_public_async(username).await
+    }
+
+    pub(super) async fn with_transport(
+        transport: Arc<dyn AsyncTransport>,
+        metrics: Metrics,
+        username: String,
+        base_url: String,
+    ) -> Result<Self, FilmwebErrors> {
+        let mut user = Self {
+            transport,
+            metrics,
+            base_url,
+            username,
+            counts: FilmwebUserCounts::default(),
+        };
+        user.counts = library::rated_counts_async(&user).await?;
+        Ok(user)
+    }
+
+    /// Origin that all requests are sent to
+    #[must_use]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Requests sent so far, per endpoint
+    #[must_use]
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// Every rated film, see [`PublicFilmwebUser::rated_films`]
+    pub async fn rated_films(&self) -> Result<Vec<FilmwebRatedTitle>, FilmwebErrors> {
+        self.fetch_library(UserPageType::RatedFilms).await
+    }
+
+    /// Every rated serial, see [`PublicFilmwebUser::rated_shows`]
+    pub async fn rated_shows(&self) -> Result<Vec<FilmwebRatedTitle>, FilmwebErrors> {
+        self.fetch_library(UserPageType::RatedShows).await
+    }
+
+    /// Every rated game, see [`PublicFilmwebUser::rated_games`]
+    pub async fn rated_games(&self) -> Result<Vec<FilmwebRatedTitle>, FilmwebErrors> {
+        self.fetch_library(UserPageType::RatedGames).await
+    }
+
+    /// Every title the user wants to see, see [`PublicFilmwebUser::watchlist`]
+    pub async fn watchlist(&self) -> Result<Vec<FilmwebRatedTitle>, FilmwebErrors> {
+        self.fetch_library(UserPageType::Watchlist).await
+    }
+
+    /// See [`PublicFilmwebUser::sorted_watchlist`]
+    pub async fn sorted_watchlist(
+        &self,
+        order: WatchlistOrder,
+    ) -> Result<Vec<FilmwebRatedTitle>, FilmwebErrors> {
+        let mut titles = self.watchlist().await?;
+        order.sort(&mut titles);
+        Ok(titles)
+    }
+
+    async fn fetch_library(
+        &self,
+        page_type: UserPageType,
+    ) -> Result<Vec<FilmwebRatedTitle>, FilmwebErrors> {
+        library::fetch_library_async(self, page_type, self.counts.of(page_type)).await
+    }
+
+    /// Scrapes a user page, see [`PublicFilmwebUser::scrape`]
+    pub async fn scrape(&self, page: UserPage) -> Result<RatedPage, FilmwebErrors> {
+        library::scrape_async(self, page).await
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncUserApi for AsyncPublicFilmwebUser {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn owner(&self) -> ListOwner<'_> {
+        ListOwner::Public(&self.username)
+    }
+
+    fn transport(&self) -> &Arc<dyn AsyncTransport> {
+        &self.transport
+    }
+
+    async fn get_text(&self, url: &str) -> Result<String, FilmwebErrors> {
+        Ok(self.transport.get(url).await?.text())
+    }
+}
+
+#[cfg(feature = "async")]
+impl User for AsyncPublicFilmwebUser {
+    fn username(&self) -> &String {
+        &self.username
+    }
+
+    fn num_of_rated_movies(&self) -> u16 {
+        self.counts.movies
+    }
+
+    fn num_of_rated_shows(&self) -> u16 {
+        self.counts.shows
+    }
+
+    fn num_of_rated_games(&self) -> u16 {
+        self.counts.games
+    }
+
+    fn num_of_watchlisted_titles(&self) -> u16 {
+        self.counts.watchlist
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::testing::FakeTransport;
+    use crate::{AlternateTitles, RatedTitle, Title, TitleType};
+
+    const FILMS_PAGE: &str = r#"<div class="myVoteBox">
+        <div class="previewFilm" data-film-id="1"></div>
+        <span class="preview__year">2021</span>
+        <a class="preview__link" href="/film/Diuna-2021-1">Diuna</a>
+        <div class="preview__detail--genres"><h3><a>Sci-Fi</a></h3></div>
+    </div>"#;
+
+    const GAMES_PAGE: &str = r#"<div class="myVoteBox">
+        <div class="previewFilm" data-film-id="2"></div>
+        <span class="preview__year">2015</span>
+        <a class="preview__link" href="/game/Wiedzmin-2015-2">Wiedźmin 3</a>
+        <div class="preview__detail--genres"><h3><a>RPG</a></h3><h3><a>Akcja</a></h3></div>
+        <div class="preview__detail--platforms"><h3><a>PC</a></h3><h3><a>PS4</a></h3></div>
+        <div class="preview__detail--developers"><h3><a>CD Projekt RED</a></h3></div>
+    </div>"#;
+
+    fn counts() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("/votes/film/count", "12"),
+            ("/votes/serial/count", "3"),
+            ("/votes/game/count", "2"),
+            ("/want2see/film/count", "4"),
+            ("/want2see/serial/count", "1"),
+        ]
+    }
+
+    /// Whether the page of the game was fetched, for a duration it doesn't have
+    fn fetched_game_page(transport: &FakeTransport) -> bool {
+        transport
+            .requests
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|request| request.url.ends_with("/game/Wiedzmin-2015-2"))
+    }
+
+    #[test]
+    fn scraping_public_profiles() {
+        let mut routes = counts();
+        routes.extend([
+            ("/user/tester/films?page=1", FILMS_PAGE),
+            ("/user/tester/games?page=1", GAMES_PAGE),
+            ("/vote/film/1/details", r#"{"rate":8}"#),
+            ("/vote/game/2/details", r#"{"rate":9}"#),
+            ("/film/Diuna-2021-1/titles", ""),
+            (
+                "/film/Diuna-2021-1",
+                r#"<div class="filmCoverSection__duration" data-duration="155"></div>"#,
+            ),
+        ]);
+        let transport = Arc::new(FakeTransport::new(routes));
+        let user = PublicFilmwebUser::builder()
+            .base_url("http://filmweb.test")
+            .transport(Arc::clone(&transport))
+            .build_public("tester")
+            .unwrap();
+
+        assert_eq!(user.username(), "tester");
+        assert_eq!(user.num_of_rated_movies(), 12);
+        assert_eq!(user.num_of_rated_games(), 2);
+        assert_eq!(user.num_of_watchlisted_titles(), 5);
+
+        let games = user.scrape(UserPage::RatedGames(1)).unwrap().rated_titles;
+        assert_eq!(games[0].title_type(), &TitleType::Game);
+        assert_eq!(games[0].rating(), Some(9));
+        assert_eq!(games[0].duration(), None);
+        assert_eq!(games[0].genres(), &vec![crate::Genre::Action]);
+        let game = games[0].game().unwrap();
+        assert_eq!(game.platforms, vec!["PC", "PS4"]);
+        assert_eq!(game.developer.as_deref(), Some("CD Projekt RED"));
+        assert!(!fetched_game_page(&transport));
+
+        let mut rated = user.scrape(UserPage::RatedFilms(1)).unwrap().rated_titles;
+        assert_eq!(rated[0].title(), "Diuna");
+        let fetched_details = || {
+            transport
+                .requests
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|request| request.url.contains("/film/Diuna-2021-1"))
+                .count()
+        };
+        assert_eq!(fetched_details(), 0);
+        assert_eq!(rated[0].duration(), Some(155));
+        assert_eq!(rated[0].duration(), Some(155));
+        assert!(rated[0].alter_titles().unwrap().is_empty());
+        assert_eq!(fetched_details(), 2);
+        assert_eq!(rated[0].rating(), Some(8));
+        assert_eq!(rated[0].favorited(), None);
+        assert!(!rated[0].is_watchlisted());
+
+        let requests = transport.requests.lock().unwrap();
+        assert!(requests.iter().all(|request| request.headers.is_empty()));
+        assert!(requests
+            .iter()
+            .any(|request| request.url
+                == "http://filmweb.test/api/v1/user/tester/vote/film/1/details"));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn fetching_details_of_async_titles_on_first_use() {
+        use crate::http::testing::Immediate;
+
+        let mut routes = counts();
+        routes.extend([
+            ("/user/tester/films?page=1", FILMS_PAGE),
+            ("/vote/film/1/details", r#"{"rate":8}"#),
+            ("/film/Diuna-2021-1/titles", ""),
+            (
+                "/film/Diuna-2021-1",
+                r#"<div class="filmCoverSection__duration" data-duration="155"></div>"#,
+            ),
+        ]);
+        let transport = Arc::new(FakeTransport::new(routes));
+        let fetched_details = || {
+            transport
+                .requests
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|request| request.url.contains("/film/Diuna-2021-1"))
+                .count()
+        };
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let user = PublicFilmwebUser::builder()
+                .base_url("http://filmweb.test")
+                .async_transport(Immediate(Arc::clone(&transport)))
+                .build_public_async("tester")
+                .await
+                .unwrap();
+            let mut rated = user.scrape(UserPage::RatedFilms(1)).await.unwrap();
+            let rated = &mut rated.rated_titles[0];
+            assert_eq!(fetched_details(), 0);
+            assert_eq!(rated.duration(), None);
+            assert_eq!(fetched_details(), 0);
+
+            assert_eq!(rated.duration_async().await, Some(155));
+            assert_eq!(rated.duration_async().await, Some(155));
+            assert_eq!(rated.duration(), Some(155));
+            assert!(rated.alter_titles_async().await.unwrap().is_empty());
+            assert_eq!(fetched_details(), 2);
+        });
+    }
+
+    #[test]
+    fn retrying_lazy_details_after_error_pages() {
+        let mut routes = counts();
+        routes.extend([
+            ("/user/tester/films?page=1", FILMS_PAGE),
+            ("/vote/film/1/details", r#"{"rate":8}"#),
+        ]);
+        let transport = Arc::new(FakeTransport::new(routes).with_status(
+            "/film/Diuna-2021-1",
+            429,
+            "<html></html>",
+        ));
+        let user = PublicFilmwebUser::builder()
+            .base_url("http://filmweb.test")
+            .transport(Arc::clone(&transport))
+            .without_retries()
+            .without_rate_limit()
+            .build_public("tester")
+            .unwrap();
+
+        let mut rated = user.scrape(UserPage::RatedFilms(1)).unwrap().rated_titles;
+        let fetched = |path: &str| {
+            transport
+                .requests
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|request| request.url.ends_with(path))
+                .count()
+        };
+        assert_eq!(rated[0].duration(), None);
+        assert_eq!(rated[0].duration(), None);
+        assert_eq!(fetched("/film/Diuna-2021-1"), 2);
+        assert!(rated[0].alter_titles().is_none());
+        assert!(rated[0].alter_titles().is_none());
+        assert_eq!(fetched("/film/Diuna-2021-1/titles"), 2);
+    }
+
+    #[test]
+    fn rejecting_private_profiles() {
+        let transport = FakeTransport::new(vec![]).with_status("/count", 403, "<html></html>");
+        let user = PublicFilmwebUser::builder()
+            .base_url("http://filmweb.test")
+            .transport(transport)
+            .build_public("hidden");
+        assert!(matches!(
+            user,
+            Err(FilmwebErrors::PrivateProfile { username }) if username == "hidden"
+        ));
+        assert_eq!(
+            parse_public_vote("tester", UserPage::Watchlist(1), r#"{"level":2}"#).unwrap(),
+            Vote::watchlisted(None, 2, 0)
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use super::FilmwebGenre;
+use super::{FilmwebGenre, FILMWEB_BASE_URL};
 use crate::Year;
 
 #[derive(Debug, Clone)]
@@ -37,22 +37,20 @@ impl QueryBuilder {
         };
 
         let genres_param = {
-            if self.genres.is_none() {
-                String::new()
-            } else {
+            if let Some(genres) = self.genres {
                 let mut genres_param = String::new();
-                for genre in self.genres.expect("Should be atleast one genre") {
+                for genre in genres {
                     genres_param.push_str(&format!("{},", genre as u8));
                 }
                 let len = genres_param.len();
                 format!("&genres={}", &genres_param[..len - 1])
+            } else {
+                String::new()
             }
         };
 
-        let url = format!(
-            "https://www.filmweb.pl/api/v1/films/search?{year_param}{genres_param}&connective=OR"
-        );
-        Query(url)
+        let path = format!("/api/v1/films/search?{year_param}{genres_param}&connective=OR");
+        Query(path)
     }
 }
 
@@ -62,13 +60,21 @@ impl Default for QueryBuilder {
     }
 }
 
+/// Search query, stored without the origin so it can be sent to any base url
 #[derive(Debug, Clone)]
 pub struct Query(String);
 
 impl Query {
+    /// Url of a given results page on filmweb.pl
     #[must_use]
     pub fn url(&self, page: u16) -> String {
-        format!("{}&page={page}", self.0)
+        self.url_on(FILMWEB_BASE_URL, page)
+    }
+
+    /// Url of a given results page on a custom base url, e.g. a mock server or a proxy
+    #[must_use]
+    pub fn url_on(&self, base_url: &str, page: u16) -> String {
+        format!("{base_url}{}&page={page}", self.0)
     }
 }
 
@@ -105,4 +111,10 @@ mod tests {
             .build();
         assert_eq!("https://www.filmweb.pl/api/v1/films/search?startYear=2021&endYear=2021&genres=13,6,33&connective=OR&page=1", query.url(1));
     }
+
+    #[test]
+    fn creating_query_on_custom_base_url() {
+        let query = QueryBuilder::new().year(Year::new(2010, 2017)).build();
+        assert_eq!("http://127.0.0.1:8080/api/v1/films/search?startYear=2010&endYear=2017&connective=OR&page=2", query.url_on("http://127.0.0.1:8080", 2));
+    }
 }
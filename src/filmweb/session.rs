@@ -0,0 +1,130 @@
+use super::auth::{FilmwebCookies, FilmwebUserCounts};
+use crate::error::SessionError;
+use crate::utils::write_private_file;
+
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+/// Everything needed to bring a [`FilmwebUser`](super::FilmwebUser) back without pasting cookies
+/// again, see [`FilmwebUser::session`](super::FilmwebUser::session) and
+/// [`FilmwebUser::from_session`](super::FilmwebUser::from_session)
+///
+/// # Examples
+/// ```rust,no_run
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use filmed::filmweb::{FilmwebSession, FilmwebUser};
+///
+/// let user = FilmwebUser::new("token", "session", "jwt")?;
+/// user.session().save("session.json")?;
+///
+/// let user = FilmwebUser::from_session(FilmwebSession::load("session.json")?)?;
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FilmwebSession {
+    pub cookies: FilmwebCookies,
+    pub username: String,
+    pub counts: FilmwebUserCounts,
+    /// When the cookies were taken from the user
+    pub captured_at: SystemTime,
+}
+
+impl FilmwebSession {
+    #[must_use]
+    pub fn new(cookies: FilmwebCookies, username: String, counts: FilmwebUserCounts) -> Self {
+        Self {
+            cookies,
+            username,
+            counts,
+            captured_at: SystemTime::now(),
+        }
+    }
+
+    /// Time since the session was captured
+    #[must_use]
+    pub fn age(&self) -> Duration {
+        self.captured_at.elapsed().unwrap_or_default()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SessionError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|source| SessionError::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        serde_json::from_str(&contents).map_err(|source| SessionError::Json {
+            path: path.to_owned(),
+            source,
+        })
+    }
+
+    /// Writes the session as JSON, replacing the file only once it's complete. On unix the file
+    /// is readable by its owner only, as cookies grant access to the account
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SessionError> {
+        let path = path.as_ref();
+        let contents = serde_json::to_vec_pretty(self).map_err(|source| SessionError::Json {
+            path: path.to_owned(),
+            source,
+        })?;
+        write_private_file(path, &contents).map_err(|source| SessionError::Io {
+            path: path.to_owned(),
+            source,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saving_and_loading_sessions() {
+        let path = std::env::temp_dir()
+            .join(format!("filmed-session-{}", std::process::id()))
+            .join("session.json");
+        let session = FilmwebSession::new(
+            FilmwebCookies::new("token", "session", "jwt"),
+            "tester".to_owned(),
+            FilmwebUserCounts {
+                movies: 1,
+                shows: 2,
+                games: 4,
+                watchlist: 3,
+            },
+        );
+        session.save(&path).unwrap();
+        assert_eq!(FilmwebSession::load(&path).unwrap(), session);
+
+        // Sessions saved before games were counted
+        let saved = fs::read_to_string(&path)
+            .unwrap()
+            .replace(r#""games": 4,"#, "");
+        assert!(!saved.contains("games"));
+        fs::write(&path, saved).unwrap();
+        assert_eq!(FilmwebSession::load(&path).unwrap().counts.games, 0);
+        assert!(session.age() < Duration::from_secs(60));
+
+        // A file written before keeps none of its old permissions
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+            session.save(&path).unwrap();
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+            assert!(!crate::utils::temp_path(&path).exists());
+        }
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert!(matches!(
+            FilmwebSession::load(&path),
+            Err(SessionError::Io { .. })
+        ));
+    }
+}
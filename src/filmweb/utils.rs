@@ -1,25 +1,41 @@
-use super::FilmwebGenre;
-use crate::{AlternateTitle, FilmwebErrors, Year};
+use super::auth::UserPage;
+use super::{FilmwebGenre, GameDetails};
+use crate::{FilmwebErrors, Year};
 
-use priority_queue::PriorityQueue;
-use reqwest::blocking::Client;
 use scraper::{ElementRef, Html, Selector};
 
 use super::STR_TO_GENRE;
 
+/// Data that can be read straight from a `div.myVoteBox`, without fetching the title's pages
 pub struct ScrapedFilmwebTitleData {
     pub id: u32,
     pub year: Year,
     pub name: String,
     pub url: String,
     pub genres: Vec<FilmwebGenre>,
-    pub alter_titles: PriorityQueue<AlternateTitle, u8>,
-    pub duration: Option<u16>, // in minutes
+    /// `Some` on a page of rated games
+    pub game: Option<GameDetails>,
+}
+
+/// Parses every `div.myVoteBox` of a user page. The document is dropped before returning, so
+/// the result can be held across `.await` points
+pub fn parse_user_page(
+    html: &str,
+    base_url: &str,
+    page: UserPage,
+) -> Result<Vec<ScrapedFilmwebTitleData>, FilmwebErrors> {
+    let document = Html::parse_document(html);
+    let is_game = matches!(page, UserPage::RatedGames(_));
+    document
+        .select(&Selector::parse("div.myVoteBox").expect("selector ok"))
+        .map(|votebox| parse_my_votebox(votebox, base_url, is_game))
+        .collect()
 }
 
 pub fn parse_my_votebox(
     votebox: ElementRef,
-    client: &Client,
+    base_url: &str,
+    is_game: bool,
 ) -> Result<ScrapedFilmwebTitleData, FilmwebErrors> {
     let id = votebox
         .select(&Selector::parse(".previewFilm").expect("selector ok"))
@@ -63,22 +79,45 @@ pub fn parse_my_votebox(
         .expect("filmweb hasnt changed")
         .inner_html();
 
-    let genres: Vec<FilmwebGenre> = votebox
-        .select(&Selector::parse(".preview__detail--genres h3 a").expect("selector ok"))
-        .into_iter()
+    let select_genres = Selector::parse(".preview__detail--genres h3 a").expect("selector ok");
+    let genres = votebox
+        .select(&select_genres)
         .inspect(|genre| {
             log::info!(target: "film_fwgenre", "fw_genre: {:?}", &genre.inner_html());
         })
-        .map(|genre| {
-            *STR_TO_GENRE
-                .get(genre.inner_html().trim().to_lowercase().as_str())
-                .expect("filmweb didnt added new genres")
-        })
-        .collect();
-    assert!(!genres.is_empty(), "There should be atleast one genre");
+        .map(|genre| genre.inner_html().trim().to_lowercase());
+    let genres: Vec<FilmwebGenre> = if is_game {
+        // Games have genres of their own, only the ones films share are kept
+        genres
+            .filter_map(|genre| STR_TO_GENRE.get(genre.as_str()).copied())
+            .collect()
+    } else {
+        let genres: Vec<FilmwebGenre> = genres
+            .map(|genre| {
+                *STR_TO_GENRE
+                    .get(genre.as_str())
+                    .expect("filmweb didnt added new genres")
+            })
+            .collect();
+        assert!(!genres.is_empty(), "There should be atleast one genre");
+        genres
+    };
+
+    let texts_of = |selector: &str| -> Vec<String> {
+        votebox
+            .select(&Selector::parse(selector).expect("selector ok"))
+            .map(|element| element.inner_html().trim().to_owned())
+            .collect()
+    };
+    let game = is_game.then(|| GameDetails {
+        platforms: texts_of(".preview__detail--platforms h3 a"),
+        developer: texts_of(".preview__detail--developers h3 a")
+            .into_iter()
+            .next(),
+    });
 
     let title_url: String = format!(
-        "https://filmweb.pl{}",
+        "{base_url}{}",
         votebox
             .select(&Selector::parse(".preview__link").expect("selector ok"))
             .next()
@@ -88,41 +127,45 @@ pub fn parse_my_votebox(
             .expect("filmweb hasnt changed")
     );
 
-    let alter_titles_url = format!("{title_url}/titles");
-    let alter_titles = AlternateTitle::fw_get_titles(&alter_titles_url, client)?;
-
-    let duration = {
-        let document = {
-            let res = client.get(&title_url).send()?.text()?;
-            Html::parse_document(&res)
-        };
-
-        document
-            .select(&Selector::parse(".filmCoverSection__duration").expect("selector ok"))
-            .next()
-            .expect("filmweb hasnt changed")
-            .value()
-            .attr("data-duration")
-            .expect("filmweb hasnt changed")
-            .parse::<u16>()
-            .map_or_else(
-                |_| {
-                    log::info!("Duration not found for {title_url}");
-                    None
-                },
-                Some,
-            )
-    };
     Ok(ScrapedFilmwebTitleData {
         id,
         year,
         genres,
         name,
         url: title_url,
-        alter_titles,
-        duration,
+        game,
     })
 }
 
+/// Reads title's duration (in minutes) from its Filmweb page, `None` when the page doesn't
+/// tell it
+pub fn parse_duration(html: &str, title_url: &str) -> Option<u16> {
+    let document = Html::parse_document(html);
+    let duration = document
+        .select(&Selector::parse(".filmCoverSection__duration").expect("selector ok"))
+        .next()
+        .and_then(|duration| duration.value().attr("data-duration"))
+        .and_then(|duration| duration.parse::<u16>().ok());
+    if duration.is_none() {
+        log::info!("Duration not found for {title_url}");
+    }
+    duration
+}
+
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_duration() {
+        let html =
+            r#"<div class="filmCoverSection__duration" data-duration="132">2 godz. 12 min.</div>"#;
+        assert_eq!(parse_duration(html, "test"), Some(132));
+        let html = r#"<div class="filmCoverSection__duration" data-duration="">-</div>"#;
+        assert_eq!(parse_duration(html, "test"), None);
+        assert_eq!(
+            parse_duration("<html>Too many requests</html>", "test"),
+            None
+        );
+    }
+}
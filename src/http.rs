@@ -0,0 +1,888 @@
+//! Every request the scrapers make goes through a [`Transport`](crate::http::Transport) (or an
+//! `AsyncTransport` with the `async` feature), so the HTTP stack can be swapped, wrapped with
+//! middleware or faked in tests. [`ReqwestTransport`](crate::http::ReqwestTransport) is
+//! used unless another one is given to a builder.
+
+mod cache;
+mod cassette;
+mod metrics;
+mod pool;
+mod rate_limit;
+mod retry;
+
+pub use crate::error::TransportError;
+pub use cache::{CacheTtls, CachedTransport, Endpoint, HttpCache};
+pub use cassette::{Cassette, CassetteTransport, Interaction, RecordedRequest, RecordedResponse};
+pub use metrics::{
+    EndpointMetrics, LatencyHistogram, MeteredTransport, Metrics, MetricsSnapshot,
+    LATENCY_BUCKETS_MS,
+};
+pub use pool::{ClientPool, Selection};
+pub use rate_limit::{
+    RateLimitedTransport, RateLimiter, DEFAULT_BURST, DEFAULT_REQUESTS_PER_SECOND,
+};
+pub use retry::{RetryPolicy, RetryTransport};
+
+use crate::error::BuildError;
+#[cfg(feature = "async")]
+use crate::utils::create_async_client;
+use crate::utils::create_client;
+use crate::USER_AGENT;
+
+use std::fmt::Debug;
+#[cfg(feature = "async")]
+use std::future::Future;
+use std::net::IpAddr;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Method {
+    Get,
+    Post,
+}
+
+impl Method {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Get => "GET",
+            Self::Post => "POST",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+impl HttpRequest {
+    #[must_use]
+    pub fn get<T: Into<String>>(url: T) -> Self {
+        Self {
+            method: Method::Get,
+            url: url.into(),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    #[must_use]
+    pub fn post<T: Into<String>>(url: T, body: Vec<u8>) -> Self {
+        Self {
+            method: Method::Post,
+            url: url.into(),
+            headers: Vec::new(),
+            body: Some(body),
+        }
+    }
+
+    #[must_use]
+    pub fn header<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// Body decoded as UTF-8, invalid sequences are replaced
+    #[must_use]
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// First value of a header, the name is case-insensitive
+    #[must_use]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Every value of a header, e.g. of `Set-Cookie`
+    pub fn header_values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.headers
+            .iter()
+            .filter(move |(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Name and value of every cookie set by the response, attributes are skipped
+    pub fn cookies(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.header_values("set-cookie").filter_map(|cookie| {
+            let pair = cookie.split(';').next()?;
+            let (name, value) = pair.split_once('=')?;
+            Some((name.trim(), value.trim()))
+        })
+    }
+
+    #[must_use]
+    pub const fn is_success(&self) -> bool {
+        self.status >= 200 && self.status < 300
+    }
+
+    /// Fails with [`TransportError::Status`] unless the status is 2xx, so an error page isn't
+    /// parsed as the page asked for
+    pub fn error_for_status(self, url: &str) -> Result<Self, TransportError> {
+        if self.is_success() {
+            Ok(self)
+        } else {
+            Err(TransportError::Status {
+                url: url.to_owned(),
+                status: self.status,
+                attempts: 1,
+            })
+        }
+    }
+}
+
+/// Sends a request and returns the response, whatever its status is
+pub trait Transport: Debug + Send + Sync {
+    fn execute(&self, request: HttpRequest) -> Result<HttpResponse, TransportError>;
+
+    fn get(&self, url: &str) -> Result<HttpResponse, TransportError> {
+        self.execute(HttpRequest::get(url))
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    fn execute(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        (**self).execute(request)
+    }
+}
+
+#[cfg(feature = "async")]
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Async flavour of [`Transport`]
+#[cfg(feature = "async")]
+pub trait AsyncTransport: Debug + Send + Sync {
+    fn execute(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, TransportError>>;
+
+    fn get(&self, url: &str) -> BoxFuture<'_, Result<HttpResponse, TransportError>> {
+        self.execute(HttpRequest::get(url))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: AsyncTransport + ?Sized> AsyncTransport for Arc<T> {
+    fn execute(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, TransportError>> {
+        (**self).execute(request)
+    }
+}
+
+impl TransportError {
+    fn from_reqwest(url: &str, error: reqwest::Error) -> Self {
+        let url = url.to_owned();
+        if error.is_timeout() {
+            Self::Timeout {
+                url,
+                source: error.into(),
+            }
+        } else if error.is_connect() {
+            Self::Connect {
+                url,
+                source: error.into(),
+            }
+        } else {
+            Self::Request {
+                url,
+                source: error.into(),
+            }
+        }
+    }
+}
+
+fn reqwest_headers(headers: &reqwest::header::HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect()
+}
+
+const fn reqwest_method(method: Method) -> reqwest::Method {
+    match method {
+        Method::Get => reqwest::Method::GET,
+        Method::Post => reqwest::Method::POST,
+    }
+}
+
+/// Timeout of a whole request unless another one is set
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Settings of the reqwest client behind a [`ReqwestTransport`]. They're checked when the
+/// client is built, so a bad proxy, header or certificate is a [`BuildError`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientOptions {
+    pub(crate) proxy: Option<String>,
+    pub(crate) local_address: Option<IpAddr>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) user_agent: String,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) root_certificates: Vec<Vec<u8>>,
+    pub(crate) built_in_root_certificates: bool,
+}
+
+impl ClientOptions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends every request through a proxy, e.g. `http://10.0.0.1:3128`
+    #[must_use]
+    pub fn proxy<T: Into<String>>(mut self, proxy: T) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Binds outgoing connections to one of the machine's addresses
+    #[must_use]
+    pub const fn local_address(mut self, address: IpAddr) -> Self {
+        self.local_address = Some(address);
+        self
+    }
+
+    /// Timeout of a whole request, [`DEFAULT_TIMEOUT`] unless changed. `None` waits forever
+    #[must_use]
+    pub const fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Timeout of connecting only
+    #[must_use]
+    pub const fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    #[must_use]
+    pub fn user_agent<T: Into<String>>(mut self, user_agent: T) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Sends a header with every request, replacing a default one with the same name (e.g.
+    /// `x-locale`)
+    #[must_use]
+    pub fn header<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Trusts PEM encoded root certificates, e.g. of an intercepting proxy
+    #[must_use]
+    pub fn root_certificate<T: Into<Vec<u8>>>(mut self, pem: T) -> Self {
+        self.root_certificates.push(pem.into());
+        self
+    }
+
+    /// Trusts only the certificates given with [`ClientOptions::root_certificate`]
+    #[must_use]
+    pub const fn only_given_root_certificates(mut self) -> Self {
+        self.built_in_root_certificates = false;
+        self
+    }
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            local_address: None,
+            timeout: Some(DEFAULT_TIMEOUT),
+            connect_timeout: None,
+            user_agent: USER_AGENT.to_owned(),
+            headers: Vec::new(),
+            root_certificates: Vec::new(),
+            built_in_root_certificates: true,
+        }
+    }
+}
+
+/// [`Transport`] backed by a blocking reqwest client
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport(reqwest::blocking::Client);
+
+impl ReqwestTransport {
+    /// Transport with the default user agent and headers Filmweb requires
+    pub fn new() -> Result<Self, BuildError> {
+        Self::with_options(&ClientOptions::default())
+    }
+
+    pub fn with_options(options: &ClientOptions) -> Result<Self, BuildError> {
+        Ok(Self(create_client(options)?))
+    }
+
+    #[must_use]
+    pub const fn from_client(client: reqwest::blocking::Client) -> Self {
+        Self(client)
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn execute(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        let HttpRequest {
+            method,
+            url,
+            headers,
+            body,
+        } = request;
+        let mut builder = self.0.request(reqwest_method(method), &url);
+        for (name, value) in headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(body) = body {
+            builder = builder.body(body);
+        }
+        let response = builder
+            .send()
+            .map_err(|e| TransportError::from_reqwest(&url, e))?;
+        let status = response.status().as_u16();
+        let headers = reqwest_headers(response.headers());
+        let body = response
+            .bytes()
+            .map_err(|e| TransportError::from_reqwest(&url, e))?
+            .to_vec();
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+/// [`AsyncTransport`] backed by an async reqwest client
+#[cfg(feature = "async")]
+#[derive(Debug, Clone)]
+pub struct AsyncReqwestTransport(reqwest::Client);
+
+#[cfg(feature = "async")]
+impl AsyncReqwestTransport {
+    /// Transport with the default user agent and headers Filmweb requires
+    pub fn new() -> Result<Self, BuildError> {
+        Self::with_options(&ClientOptions::default())
+    }
+
+    pub fn with_options(options: &ClientOptions) -> Result<Self, BuildError> {
+        Ok(Self(create_async_client(options)?))
+    }
+
+    #[must_use]
+    pub const fn from_client(client: reqwest::Client) -> Self {
+        Self(client)
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncTransport for AsyncReqwestTransport {
+    fn execute(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, TransportError>> {
+        Box::pin(async move {
+            let HttpRequest {
+                method,
+                url,
+                headers,
+                body,
+            } = request;
+            let mut builder = self.0.request(reqwest_method(method), &url);
+            for (name, value) in headers {
+                builder = builder.header(name, value);
+            }
+            if let Some(body) = body {
+                builder = builder.body(body);
+            }
+            let response = builder
+                .send()
+                .await
+                .map_err(|e| TransportError::from_reqwest(&url, e))?;
+            let status = response.status().as_u16();
+            let headers = reqwest_headers(response.headers());
+            let body = response
+                .bytes()
+                .await
+                .map_err(|e| TransportError::from_reqwest(&url, e))?
+                .to_vec();
+            Ok(HttpResponse {
+                status,
+                headers,
+                body,
+            })
+        })
+    }
+}
+
+/// Transport settings shared by every builder: the transport requests are sent through and the
+/// middleware wrapped around it. Builders have a setter for each of them as well, or take them
+/// all at once, e.g. to configure Filmweb and IMDb alike
+#[derive(Debug, Clone)]
+pub struct TransportOptions {
+    pub(crate) transport: Option<Arc<dyn Transport>>,
+    #[cfg(feature = "async")]
+    pub(crate) async_transport: Option<Arc<dyn AsyncTransport>>,
+    pub(crate) rate_limiter: Option<RateLimiter>,
+    pub(crate) retry_policy: Option<RetryPolicy>,
+    pub(crate) cache: Option<HttpCache>,
+    pub(crate) metrics: Metrics,
+    /// Used by the default transport only
+    pub(crate) client_options: ClientOptions,
+}
+
+impl TransportOptions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            transport: None,
+            #[cfg(feature = "async")]
+            async_transport: None,
+            rate_limiter: Some(RateLimiter::default()),
+            retry_policy: Some(RetryPolicy::default()),
+            cache: None,
+            metrics: Metrics::new(),
+            client_options: ClientOptions::default(),
+        }
+    }
+
+    /// Configures the default reqwest client, see [`ClientOptions`]. Ignored when a transport
+    /// is given
+    #[must_use]
+    pub fn client_options(mut self, options: ClientOptions) -> Self {
+        self.client_options = options;
+        self
+    }
+
+    /// Timeout of a whole request, [`DEFAULT_TIMEOUT`] unless changed
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.client_options = self.client_options.timeout(Some(timeout));
+        self
+    }
+
+    /// Sends every request through a proxy, e.g. `http://10.0.0.1:3128`
+    #[must_use]
+    pub fn proxy<T: Into<String>>(mut self, proxy: T) -> Self {
+        self.client_options = self.client_options.proxy(proxy);
+        self
+    }
+
+    #[must_use]
+    pub fn user_agent<T: Into<String>>(mut self, user_agent: T) -> Self {
+        self.client_options = self.client_options.user_agent(user_agent);
+        self
+    }
+
+    /// Sends a header with every request, replacing a default one with the same name
+    #[must_use]
+    pub fn header<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.client_options = self.client_options.header(name, value);
+        self
+    }
+
+    /// Trusts a PEM encoded root certificate
+    #[must_use]
+    pub fn root_certificate<T: Into<Vec<u8>>>(mut self, pem: T) -> Self {
+        self.client_options = self.client_options.root_certificate(pem);
+        self
+    }
+
+    /// Sends requests through `transport` instead of a [`ReqwestTransport`], e.g. through a
+    /// [`ClientPool`]. Filmweb users add their cookies to each request, so the transport
+    /// doesn't need to keep them
+    #[must_use]
+    pub fn transport<T: Transport + 'static>(mut self, transport: T) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Sends requests of the async flavours through `transport` instead of an
+    /// [`AsyncReqwestTransport`]
+    #[cfg(feature = "async")]
+    #[must_use]
+    pub fn async_transport<T: AsyncTransport + 'static>(mut self, transport: T) -> Self {
+        self.async_transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Throttles requests with `limiter` instead of a default [`RateLimiter`]. Give clones of
+    /// one limiter to several builders, e.g. of Filmweb and IMDb, to share its limits
+    #[must_use]
+    pub fn rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Sends requests as fast as the transport can
+    #[must_use]
+    pub fn without_rate_limit(mut self) -> Self {
+        self.rate_limiter = None;
+        self
+    }
+
+    /// Retries failed GET requests according to `policy` instead of [`RetryPolicy::default`]
+    #[must_use]
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Makes a single attempt at every request
+    #[must_use]
+    pub fn without_retries(mut self) -> Self {
+        self.retry_policy = None;
+        self
+    }
+
+    /// Keeps responses that rarely change in `cache`, see [`HttpCache`]
+    #[must_use]
+    pub fn cache(mut self, cache: HttpCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Collects request counters in `metrics` instead of a collector of its own. Give clones
+    /// of one collector to several builders to account for all of them together
+    #[must_use]
+    pub fn metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Wraps the given transport, or a [`ReqwestTransport`] with the client options, with the
+    /// middleware
+    pub(crate) fn build(self) -> Result<Arc<dyn Transport>, BuildError> {
+        let mut transport = match self.transport {
+            Some(transport) => transport,
+            None => Arc::new(ReqwestTransport::with_options(&self.client_options)?),
+        };
+        // Innermost, so every attempt is counted and waiting for the limiter isn't latency
+        transport = Arc::new(MeteredTransport::new(transport, self.metrics));
+        if let Some(limiter) = self.rate_limiter {
+            transport = Arc::new(RateLimitedTransport::new(transport, limiter));
+        }
+        // Outside of the limiter, so every attempt waits for its turn
+        if let Some(policy) = self.retry_policy {
+            transport = Arc::new(RetryTransport::new(transport, policy));
+        }
+        // Outermost, so cached responses aren't rate limited
+        if let Some(cache) = self.cache {
+            transport = Arc::new(CachedTransport::new(transport, cache));
+        }
+        Ok(transport)
+    }
+
+    /// Async flavour of [`TransportOptions::build`]
+    #[cfg(feature = "async")]
+    pub(crate) fn build_async(self) -> Result<Arc<dyn AsyncTransport>, BuildError> {
+        let mut transport = match self.async_transport {
+            Some(transport) => transport,
+            None => Arc::new(AsyncReqwestTransport::with_options(&self.client_options)?),
+        };
+        transport = Arc::new(MeteredTransport::new(transport, self.metrics));
+        if let Some(limiter) = self.rate_limiter {
+            transport = Arc::new(RateLimitedTransport::new(transport, limiter));
+        }
+        // Outside of the limiter, so every attempt waits for its turn
+        if let Some(policy) = self.retry_policy {
+            transport = Arc::new(RetryTransport::new(transport, policy));
+        }
+        // Outermost, so cached responses aren't rate limited
+        if let Some(cache) = self.cache {
+            transport = Arc::new(CachedTransport::new(transport, cache));
+        }
+        Ok(transport)
+    }
+}
+
+impl Default for TransportOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Adds the setters of [`TransportOptions`] to a builder keeping them in its `options` field
+macro_rules! transport_setters {
+    () => {
+        /// Replaces every transport setting at once, see
+        /// [`TransportOptions`](crate::http::TransportOptions)
+        #[must_use]
+        pub fn transport_options(mut self, options: $crate::http::TransportOptions) -> Self {
+            self.options = options;
+            self
+        }
+
+        /// See [`TransportOptions::client_options`](crate::http::TransportOptions::client_options)
+        #[must_use]
+        pub fn client_options(mut self, options: $crate::http::ClientOptions) -> Self {
+            self.options = self.options.client_options(options);
+            self
+        }
+
+        /// See [`TransportOptions::timeout`](crate::http::TransportOptions::timeout)
+        #[must_use]
+        pub fn timeout(mut self, timeout: ::std::time::Duration) -> Self {
+            self.options = self.options.timeout(timeout);
+            self
+        }
+
+        /// See [`TransportOptions::proxy`](crate::http::TransportOptions::proxy)
+        #[must_use]
+        pub fn proxy<T: Into<String>>(mut self, proxy: T) -> Self {
+            self.options = self.options.proxy(proxy);
+            self
+        }
+
+        /// See [`TransportOptions::user_agent`](crate::http::TransportOptions::user_agent)
+        #[must_use]
+        pub fn user_agent<T: Into<String>>(mut self, user_agent: T) -> Self {
+            self.options = self.options.user_agent(user_agent);
+            self
+        }
+
+        /// See [`TransportOptions::header`](crate::http::TransportOptions::header)
+        #[must_use]
+        pub fn header<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+            self.options = self.options.header(name, value);
+            self
+        }
+
+        /// See [`TransportOptions::root_certificate`](crate::http::TransportOptions::root_certificate)
+        #[must_use]
+        pub fn root_certificate<T: Into<Vec<u8>>>(mut self, pem: T) -> Self {
+            self.options = self.options.root_certificate(pem);
+            self
+        }
+
+        /// See [`TransportOptions::transport`](crate::http::TransportOptions::transport)
+        #[must_use]
+        pub fn transport<T: $crate::http::Transport + 'static>(mut self, transport: T) -> Self {
+            self.options = self.options.transport(transport);
+            self
+        }
+
+        /// See [`TransportOptions::async_transport`](crate::http::TransportOptions::async_transport)
+        #[cfg(feature = "async")]
+        #[must_use]
+        pub fn async_transport<T: $crate::http::AsyncTransport + 'static>(
+            mut self,
+            transport: T,
+        ) -> Self {
+            self.options = self.options.async_transport(transport);
+            self
+        }
+
+        /// See [`TransportOptions::rate_limiter`](crate::http::TransportOptions::rate_limiter)
+        #[must_use]
+        pub fn rate_limiter(mut self, limiter: $crate::http::RateLimiter) -> Self {
+            self.options = self.options.rate_limiter(limiter);
+            self
+        }
+
+        /// See [`TransportOptions::without_rate_limit`](crate::http::TransportOptions::without_rate_limit)
+        #[must_use]
+        pub fn without_rate_limit(mut self) -> Self {
+            self.options = self.options.without_rate_limit();
+            self
+        }
+
+        /// See [`TransportOptions::retry_policy`](crate::http::TransportOptions::retry_policy)
+        #[must_use]
+        pub fn retry_policy(mut self, policy: $crate::http::RetryPolicy) -> Self {
+            self.options = self.options.retry_policy(policy);
+            self
+        }
+
+        /// See [`TransportOptions::without_retries`](crate::http::TransportOptions::without_retries)
+        #[must_use]
+        pub fn without_retries(mut self) -> Self {
+            self.options = self.options.without_retries();
+            self
+        }
+
+        /// See [`TransportOptions::cache`](crate::http::TransportOptions::cache)
+        #[must_use]
+        pub fn cache(mut self, cache: $crate::http::HttpCache) -> Self {
+            self.options = self.options.cache(cache);
+            self
+        }
+
+        /// See [`TransportOptions::metrics`](crate::http::TransportOptions::metrics)
+        #[must_use]
+        pub fn metrics(mut self, metrics: $crate::http::Metrics) -> Self {
+            self.options = self.options.metrics(metrics);
+            self
+        }
+    };
+}
+pub(crate) use transport_setters;
+
+/// Fake transports for tests that shouldn't touch the network
+#[cfg(test)]
+pub mod testing {
+    #[cfg(feature = "async")]
+    use super::{AsyncReqwestTransport, AsyncTransport, BoxFuture};
+    use super::{
+        CassetteTransport, HttpRequest, HttpResponse, ReqwestTransport, Transport, TransportError,
+    };
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+
+    /// Set to record cassettes that don't exist yet from the live site
+    const RECORD_VAR: &str = "FILMED_RECORD";
+
+    fn cassette_path(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/cassettes")
+            .join(format!("{name}.json"))
+    }
+
+    /// Whether the cassette at `path` is replayed. Panics when it's missing and recording it
+    /// wasn't asked for
+    fn is_recorded(path: &Path) -> bool {
+        if path.exists() {
+            return true;
+        }
+        assert!(
+            std::env::var_os(RECORD_VAR).is_some(),
+            "{} is missing, set {RECORD_VAR}=1 to record it from the live site",
+            path.display()
+        );
+        false
+    }
+
+    /// Replays `tests/cassettes/{name}.json`, so tests that scrape Filmweb or `IMDb` run
+    /// offline. A missing cassette fails the test, unless `FILMED_RECORD` is set to record it
+    pub fn cassette(name: &str) -> CassetteTransport {
+        let path = cassette_path(name);
+        if is_recorded(&path) {
+            CassetteTransport::replay(path).expect("cassette is valid")
+        } else {
+            CassetteTransport::record(ReqwestTransport::new().expect("can create a client"), path)
+        }
+    }
+
+    /// Async flavour of [`cassette`]
+    #[cfg(feature = "async")]
+    pub fn async_cassette(name: &str) -> CassetteTransport {
+        let path = cassette_path(name);
+        if is_recorded(&path) {
+            CassetteTransport::replay(path).expect("cassette is valid")
+        } else {
+            CassetteTransport::record_async(
+                AsyncReqwestTransport::new().expect("can create a client"),
+                path,
+            )
+        }
+    }
+
+    /// Answers with the body of the first route whose url contains it, or with a 404. Every
+    /// request is kept, so tests can check what was sent
+    #[derive(Debug, Default)]
+    pub struct FakeTransport {
+        routes: Vec<(String, HttpResponse)>,
+        pub requests: Mutex<Vec<HttpRequest>>,
+    }
+
+    impl FakeTransport {
+        pub fn new(routes: Vec<(&str, &str)>) -> Self {
+            Self {
+                routes: routes
+                    .into_iter()
+                    .map(|(route, body)| (route.to_owned(), response(200, body)))
+                    .collect(),
+                requests: Mutex::default(),
+            }
+        }
+
+        pub fn with_status(self, route: &str, status: u16, body: &str) -> Self {
+            self.with_response(route, response(status, body))
+        }
+
+        /// Answers requests to `route` with `response`, taking precedence over earlier routes
+        pub fn with_response(mut self, route: &str, response: HttpResponse) -> Self {
+            self.routes.insert(0, (route.to_owned(), response));
+            self
+        }
+    }
+
+    pub fn response(status: u16, body: &str) -> HttpResponse {
+        HttpResponse {
+            status,
+            headers: Vec::new(),
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    impl Transport for FakeTransport {
+        fn execute(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+            let response = self
+                .routes
+                .iter()
+                .find(|(route, _)| request.url.contains(route.as_str()))
+                .map_or_else(|| response(404, ""), |(_, response)| response.clone());
+            self.requests.lock().unwrap().push(request);
+            Ok(response)
+        }
+    }
+
+    /// Async flavour of a transport that answers right away, e.g. of a [`FakeTransport`]
+    #[cfg(feature = "async")]
+    #[derive(Debug)]
+    pub struct Immediate<T>(pub T);
+
+    #[cfg(feature = "async")]
+    impl<T: Transport> AsyncTransport for Immediate<T> {
+        fn execute(
+            &self,
+            request: HttpRequest,
+        ) -> BoxFuture<'_, Result<HttpResponse, TransportError>> {
+            Box::pin(async move { self.0.execute(request) })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reading_response_headers() {
+        let response = HttpResponse {
+            status: 200,
+            headers: vec![
+                ("set-cookie".to_owned(), "JWT=abc; Path=/".to_owned()),
+                ("Set-Cookie".to_owned(), "_fwuser_sessionId=def".to_owned()),
+            ],
+            body: "ok".into(),
+        };
+        assert!(response.is_success());
+        assert_eq!(response.header("SET-COOKIE"), Some("JWT=abc; Path=/"));
+        assert_eq!(response.header_values("set-cookie").count(), 2);
+        assert_eq!(response.header("content-type"), None);
+        assert_eq!(
+            response.cookies().collect::<Vec<_>>(),
+            vec![("JWT", "abc"), ("_fwuser_sessionId", "def")]
+        );
+    }
+}
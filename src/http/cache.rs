@@ -0,0 +1,409 @@
+//! Persistent cache of responses that rarely change, so re-running an export doesn't download
+//! every title again. Each kind of resource ([`Endpoint`]) has its own time to live, those
+//! without one aren't cached at all.
+
+#[cfg(feature = "async")]
+use super::{AsyncTransport, BoxFuture};
+use super::{HttpRequest, HttpResponse, Method, Transport, TransportError};
+use crate::filmweb::JwtClaims;
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Kind of resource a request asks for, read from its url
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Endpoint {
+    /// `/api/v1/{film,serial,game}/{id}/preview`, or `/api/v1/films/previews` of a batch
+    Preview,
+    /// `/{film,serial,game}/{title}/titles`
+    AlternateTitles,
+    /// `/{film,serial,game}/{title}`, fetched for the duration
+    TitlePage,
+    /// `/title/{imdb id}/`
+    IMDbTitlePage,
+    /// `/find` and `/search/title/`
+    IMDbSearch,
+    /// `/api/v1/logged/vote/{film,serial}/{id}/details`, user's own rating of a title, or
+    /// `/api/v1/user/{username}/vote/{film,serial}/{id}/details` of a public profile. Likewise
+    /// `want2see` details of a title on a watchlist
+    VoteDetails,
+    /// Anything else, e.g. Filmweb search results, user pages, vote lists and counts
+    Other,
+}
+
+impl Endpoint {
+    #[must_use]
+    pub fn of(url: &str) -> Self {
+        let Ok(url) = url::Url::parse(url) else {
+            return Self::Other;
+        };
+        let segments: Vec<&str> = url
+            .path_segments()
+            .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default();
+        let is_title_kind = |segment: &str| matches!(segment, "film" | "serial" | "game");
+        match segments.as_slice() {
+            ["api", "v1", kind, _, "preview"] if is_title_kind(kind) => Self::Preview,
+            ["api", "v1", "films", "previews"] => Self::Preview,
+            ["api", "v1", "logged", "vote" | "want2see", _, _, "details"]
+            | ["api", "v1", "user", _, "vote" | "want2see", _, _, "details"] => Self::VoteDetails,
+            [kind, _, "titles"] if is_title_kind(kind) => Self::AlternateTitles,
+            [kind, _] if is_title_kind(kind) => Self::TitlePage,
+            ["title", id] if id.starts_with("tt") => Self::IMDbTitlePage,
+            ["find"] | ["search", "title"] => Self::IMDbSearch,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Time to live of every [`Endpoint`]. Vote details change whenever the user rates a title
+/// again, so they're kept only for an hour
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheTtls {
+    pub preview: Option<Duration>,
+    pub alternate_titles: Option<Duration>,
+    pub title_page: Option<Duration>,
+    pub imdb_title_page: Option<Duration>,
+    pub vote_details: Option<Duration>,
+}
+
+impl CacheTtls {
+    #[must_use]
+    pub const fn get(&self, endpoint: Endpoint) -> Option<Duration> {
+        match endpoint {
+            Endpoint::Preview => self.preview,
+            Endpoint::AlternateTitles => self.alternate_titles,
+            Endpoint::TitlePage => self.title_page,
+            Endpoint::IMDbTitlePage => self.imdb_title_page,
+            Endpoint::VoteDetails => self.vote_details,
+            Endpoint::IMDbSearch | Endpoint::Other => None,
+        }
+    }
+}
+
+impl Default for CacheTtls {
+    fn default() -> Self {
+        Self {
+            preview: Some(DAY * 30),
+            alternate_titles: Some(DAY * 30),
+            title_page: Some(DAY * 30),
+            imdb_title_page: Some(DAY * 30),
+            vote_details: Some(Duration::from_secs(60 * 60)),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct Entry {
+    url: String,
+    stored_at: u64,
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+/// Responses kept in a directory, one file per request
+///
+/// # Examples
+/// ```rust,no_run
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use std::time::Duration;
+/// use filmed::filmweb::Filmweb;
+/// use filmed::http::HttpCache;
+///
+/// let cache = HttpCache::new(".cache/filmed").ttls(|ttls| {
+///     ttls.vote_details = Some(Duration::from_secs(10 * 60));
+/// });
+/// let fw = Filmweb::builder().cache(cache).build()?;
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    dir: PathBuf,
+    ttls: CacheTtls,
+}
+
+impl HttpCache {
+    #[must_use]
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Self {
+            dir: dir.into(),
+            ttls: CacheTtls::default(),
+        }
+    }
+
+    /// Changes the default [`CacheTtls`]
+    #[must_use]
+    pub fn ttls<F: FnOnce(&mut CacheTtls)>(mut self, change: F) -> Self {
+        change(&mut self.ttls);
+        self
+    }
+
+    /// Removes every cached response
+    pub fn clear(&self) -> std::io::Result<()> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Where the response to `request` is kept, if it can be cached at all. Responses of the
+    /// logged-in api are only shared by requests of the same user, told by the user id of the
+    /// `JWT` cookie rather than by the cookies, which change whenever the JWT is renewed. The
+    /// rest doesn't depend on cookies, so it's shared by every account
+    fn entry_path(&self, request: &HttpRequest) -> Option<(PathBuf, Duration)> {
+        if request.method != Method::Get {
+            return None;
+        }
+        let ttl = self.ttls.get(Endpoint::of(&request.url))?;
+        let mut key = request.url.clone();
+        if is_logged_in_api(&request.url) {
+            key.push_str(&format!("\nuser {}", cookie_user_id(request)?));
+        }
+        Some((self.dir.join(format!("{:016x}.json", fnv1a(&key))), ttl))
+    }
+
+    fn load(&self, request: &HttpRequest) -> Option<HttpResponse> {
+        let (path, ttl) = self.entry_path(request)?;
+        let entry: Entry = serde_json::from_slice(&fs::read(path).ok()?).ok()?;
+        let age = now().saturating_sub(entry.stored_at);
+        if entry.url != request.url || age > ttl.as_secs() {
+            return None;
+        }
+        log::debug!("Cache hit for {}", request.url);
+        Some(HttpResponse {
+            status: entry.status,
+            headers: entry.headers,
+            body: entry.body,
+        })
+    }
+
+    fn store(&self, request: &HttpRequest, response: &HttpResponse) {
+        let Some((path, _)) = self.entry_path(request) else {
+            return;
+        };
+        if !response.is_success() {
+            return;
+        }
+        let entry = Entry {
+            url: request.url.clone(),
+            stored_at: now(),
+            status: response.status,
+            headers: response
+                .headers
+                .iter()
+                .filter(|(name, _)| !name.eq_ignore_ascii_case("set-cookie"))
+                .cloned()
+                .collect(),
+            body: response.body.clone(),
+        };
+        let stored = fs::create_dir_all(&self.dir).and_then(|()| {
+            fs::write(
+                &path,
+                serde_json::to_vec(&entry).expect("entry is serializable"),
+            )
+        });
+        if let Err(e) = stored {
+            log::warn!("Couldn't cache {}: {e}", request.url);
+        }
+    }
+}
+
+/// Whether `url` is of `/api/v1/logged/`, answered for the user whose cookies are sent
+fn is_logged_in_api(url: &str) -> bool {
+    url::Url::parse(url).is_ok_and(|url| url.path().starts_with("/api/v1/logged/"))
+}
+
+/// Id of the user the `JWT` cookie was issued to
+fn cookie_user_id(request: &HttpRequest) -> Option<u64> {
+    request
+        .headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("cookie"))
+        .flat_map(|(_, value)| value.split(';'))
+        .find_map(|cookie| cookie.trim().strip_prefix("JWT="))
+        .and_then(|jwt| JwtClaims::decode(jwt).ok()?.user_id)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+/// Stable across builds, unlike `DefaultHasher`, so cache files outlive a toolchain upgrade
+fn fnv1a(key: &str) -> u64 {
+    key.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Answers from an [`HttpCache`] when it can, and fills it otherwise
+#[derive(Debug)]
+pub struct CachedTransport<T: ?Sized> {
+    cache: HttpCache,
+    inner: Arc<T>,
+}
+
+impl<T: ?Sized> CachedTransport<T> {
+    pub fn new(inner: Arc<T>, cache: HttpCache) -> Self {
+        Self { cache, inner }
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for CachedTransport<T> {
+    fn execute(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        if let Some(response) = self.cache.load(&request) {
+            return Ok(response);
+        }
+        let response = self.inner.execute(request.clone())?;
+        self.cache.store(&request, &response);
+        Ok(response)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: AsyncTransport + ?Sized> AsyncTransport for CachedTransport<T> {
+    fn execute(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, TransportError>> {
+        Box::pin(async move {
+            if let Some(response) = self.cache.load(&request) {
+                return Ok(response);
+            }
+            let response = self.inner.execute(request.clone()).await?;
+            self.cache.store(&request, &response);
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::testing::FakeTransport;
+
+    #[test]
+    fn classifying_endpoints() {
+        let fw = "https://www.filmweb.pl";
+        assert_eq!(
+            Endpoint::of(&format!("{fw}/api/v1/film/1/preview")),
+            Endpoint::Preview
+        );
+        assert_eq!(
+            Endpoint::of(&format!("{fw}/api/v1/films/previews?ids=1,2")),
+            Endpoint::Preview
+        );
+        assert_eq!(
+            Endpoint::of(&format!("{fw}/serial/Dark-2017-778139/titles")),
+            Endpoint::AlternateTitles
+        );
+        assert_eq!(
+            Endpoint::of(&format!("{fw}/film/Diuna-2021-1")),
+            Endpoint::TitlePage
+        );
+        assert_eq!(
+            Endpoint::of(&format!("{fw}/api/v1/logged/vote/film/1/details")),
+            Endpoint::VoteDetails
+        );
+        assert_eq!(
+            Endpoint::of(&format!("{fw}/api/v1/user/tester/vote/serial/1/details")),
+            Endpoint::VoteDetails
+        );
+        assert_eq!(
+            Endpoint::of(&format!("{fw}/api/v1/logged/want2see/film/1/details")),
+            Endpoint::VoteDetails
+        );
+        assert_eq!(
+            Endpoint::of("https://www.imdb.com/title/tt0371246/"),
+            Endpoint::IMDbTitlePage
+        );
+        assert_eq!(
+            Endpoint::of(&format!("{fw}/user/tester/films?page=1")),
+            Endpoint::Other
+        );
+        assert_eq!(
+            Endpoint::of("https://www.imdb.com/find?q=Stay"),
+            Endpoint::IMDbSearch
+        );
+    }
+
+    #[test]
+    fn caching_responses() {
+        let dir = std::env::temp_dir().join(format!("filmed-cache-{}", std::process::id()));
+        let cache = HttpCache::new(&dir).ttls(|ttls| ttls.vote_details = None);
+        let fake = Arc::new(FakeTransport::new(vec![
+            ("/preview", "preview"),
+            ("/details", "details"),
+            ("/search", "search"),
+        ]));
+        let transport = CachedTransport::new(Arc::clone(&fake), cache.clone());
+
+        let preview = "http://fw.test/api/v1/film/1/preview";
+        let details = "http://fw.test/api/v1/logged/vote/film/1/details";
+        for _ in 0..2 {
+            assert_eq!(transport.get(preview).unwrap().text(), "preview");
+            assert_eq!(transport.get(details).unwrap().text(), "details");
+            assert_eq!(
+                transport.get("http://fw.test/search").unwrap().text(),
+                "search"
+            );
+        }
+        // Previews come from the cache the second time, the rest isn't cached
+        assert_eq!(fake.requests.lock().unwrap().len(), 5);
+
+        // Previews don't depend on whose cookies are sent
+        let request = HttpRequest::get(preview).header("Cookie", "JWT=other");
+        transport.execute(request).unwrap();
+        assert_eq!(fake.requests.lock().unwrap().len(), 5);
+
+        cache.clear().unwrap();
+        transport.get(preview).unwrap();
+        assert_eq!(fake.requests.lock().unwrap().len(), 6);
+        cache.clear().unwrap();
+    }
+
+    #[test]
+    fn keying_logged_in_responses_by_user() {
+        use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+        let dir = std::env::temp_dir().join(format!("filmed-cache-user-{}", std::process::id()));
+        let cache = HttpCache::new(&dir);
+        let fake = Arc::new(FakeTransport::new(vec![("/details", "details")]));
+        let transport = CachedTransport::new(Arc::clone(&fake), cache.clone());
+        let details = "http://fw.test/api/v1/logged/vote/film/1/details";
+        let sent = || fake.requests.lock().unwrap().len();
+        let get = |cookie: Option<String>| {
+            let request = HttpRequest::get(details);
+            let request = match cookie {
+                Some(cookie) => request.header("Cookie", cookie),
+                None => request,
+            };
+            transport.execute(request).unwrap();
+        };
+        let cookie = |user_id: u64, issued_at: u64| {
+            let claims = format!(r#"{{"uid":{user_id},"iat":{issued_at}}}"#);
+            let jwt = format!("eyJh.{}.sig", URL_SAFE_NO_PAD.encode(claims));
+            Some(format!("_fwuser_sessionId=s{issued_at}; JWT={jwt};"))
+        };
+
+        get(cookie(1, 100));
+        // A renewed JWT of the same user hits the cache, another user's doesn't
+        get(cookie(1, 200));
+        assert_eq!(sent(), 1);
+        get(cookie(2, 100));
+        assert_eq!(sent(), 2);
+        // Without a user to key them by, responses aren't cached
+        get(None);
+        get(None);
+        assert_eq!(sent(), 4);
+        cache.clear().unwrap();
+    }
+}
@@ -0,0 +1,514 @@
+//! Record/replay of HTTP interactions. A [`CassetteTransport`] in record mode forwards every
+//! request to another transport and keeps the exchange, which is written to a JSON cassette
+//! file. In replay mode the cassette answers the requests and nothing is sent over the network.
+//!
+//! Cookies are secrets, so `Cookie` request headers aren't stored and `Set-Cookie` values are
+//! redacted. Bodies are kept as text, which is fine for the HTML and JSON Filmweb and `IMDb`
+//! serve, with passwords and tokens in JSON bodies and bare JWT bodies redacted as well.
+
+#[cfg(feature = "async")]
+use super::{AsyncTransport, BoxFuture};
+use super::{HttpRequest, HttpResponse, Method, Transport, TransportError};
+use crate::error::CassetteError;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+/// Request headers that never end up in a cassette
+const SECRET_HEADERS: [&str; 2] = ["cookie", "authorization"];
+
+/// Fields of JSON bodies whose values never end up in a cassette, compared case-insensitively
+const SECRET_FIELDS: [&str; 5] = ["password", "token", "jwt", "accesstoken", "refreshtoken"];
+
+/// Stands in for a redacted JWT, still shaped like one so it can be replayed
+const REDACTED_JWT: &str = "REDACTED.REDACTED.REDACTED";
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct RecordedRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+impl Interaction {
+    fn new(request: &HttpRequest, response: &HttpResponse) -> Self {
+        let request = RecordedRequest {
+            method: request.method,
+            url: request.url.clone(),
+            headers: request
+                .headers
+                .iter()
+                .filter(|(name, _)| !SECRET_HEADERS.contains(&name.to_lowercase().as_str()))
+                .cloned()
+                .collect(),
+            body: request.body.as_deref().map(redact_body),
+        };
+        let response = RecordedResponse {
+            status: response.status,
+            headers: response
+                .headers
+                .iter()
+                .map(|(name, value)| {
+                    if name.eq_ignore_ascii_case("set-cookie") {
+                        (name.clone(), redact_set_cookie(value))
+                    } else {
+                        (name.clone(), value.clone())
+                    }
+                })
+                .collect(),
+            body: redact_body(&response.body),
+        };
+        Self { request, response }
+    }
+
+    fn matches(&self, request: &HttpRequest) -> bool {
+        self.request.method == request.method
+            && self.request.url == request.url
+            && self.request.body == request.body.as_deref().map(redact_body)
+    }
+
+    fn to_response(&self) -> HttpResponse {
+        HttpResponse {
+            status: self.response.status,
+            headers: self.response.headers.clone(),
+            body: self.response.body.clone().into_bytes(),
+        }
+    }
+}
+
+/// `JWT=abc; Path=/` becomes `JWT=REDACTED; Path=/`
+fn redact_set_cookie(value: &str) -> String {
+    let (cookie, attributes) = value
+        .split_once(';')
+        .map_or((value, None), |(cookie, rest)| (cookie, Some(rest)));
+    let name = cookie.split_once('=').map_or(cookie, |(name, _)| name);
+    attributes.map_or_else(
+        || format!("{name}=REDACTED"),
+        |attributes| format!("{name}=REDACTED;{attributes}"),
+    )
+}
+
+/// Replaces secrets in a body, e.g. the password of a login or a JWT sent as the whole body
+fn redact_body(body: &[u8]) -> String {
+    let text = String::from_utf8_lossy(body);
+    if is_jwt(&text) {
+        return REDACTED_JWT.to_owned();
+    }
+    let Ok(mut json) = serde_json::from_str::<serde_json::Value>(&text) else {
+        return text.into_owned();
+    };
+    if redact_json(&mut json) {
+        json.to_string()
+    } else {
+        text.into_owned()
+    }
+}
+
+/// Redacts secret fields at any depth, returns whether anything was redacted
+fn redact_json(json: &mut serde_json::Value) -> bool {
+    match json {
+        serde_json::Value::Object(fields) => {
+            let mut redacted = false;
+            for (name, value) in fields.iter_mut() {
+                if SECRET_FIELDS.contains(&name.to_lowercase().as_str()) {
+                    *value = serde_json::Value::from("REDACTED");
+                    redacted = true;
+                } else {
+                    redacted |= redact_json(value);
+                }
+            }
+            redacted
+        }
+        serde_json::Value::Array(values) => values
+            .iter_mut()
+            .fold(false, |redacted, value| redact_json(value) | redacted),
+        _ => false,
+    }
+}
+
+/// Three base64url segments, optionally quoted as a JSON string
+fn is_jwt(text: &str) -> bool {
+    let segments: Vec<&str> = text.trim().trim_matches('"').split('.').collect();
+    segments.len() == 3
+        && segments.iter().all(|segment| {
+            !segment.is_empty()
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+}
+
+/// Interactions saved in a cassette file
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, CassetteError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|source| CassetteError::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        serde_json::from_str(&contents).map_err(|source| CassetteError::Json {
+            path: path.to_owned(),
+            source,
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), CassetteError> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|source| CassetteError::Io {
+                path: path.to_owned(),
+                source,
+            })?;
+        }
+        let contents =
+            serde_json::to_string_pretty(self).map_err(|source| CassetteError::Json {
+                path: path.to_owned(),
+                source,
+            })?;
+        fs::write(path, contents).map_err(|source| CassetteError::Io {
+            path: path.to_owned(),
+            source,
+        })
+    }
+}
+
+#[derive(Debug)]
+enum Mode {
+    Record(Arc<dyn Transport>),
+    #[cfg(feature = "async")]
+    RecordAsync(Arc<dyn AsyncTransport>),
+    Replay,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    cassette: Cassette,
+    /// Interactions already served, so repeated requests are replayed in the recorded order
+    used: Vec<bool>,
+    dirty: bool,
+}
+
+/// Records interactions to, or replays them from, a cassette file
+///
+/// # Examples
+/// ```rust,no_run
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use std::sync::Arc;
+/// use filmed::filmweb::Filmweb;
+/// use filmed::http::{CassetteTransport, ReqwestTransport};
+///
+/// let cassette = Arc::new(CassetteTransport::record(ReqwestTransport::new()?, "fw.json"));
+/// let fw = Filmweb::builder().transport(Arc::clone(&cassette)).build()?;
+/// // ... scrape, then write what was sent and received
+/// cassette.save()?;
+///
+/// let fw = Filmweb::builder()
+///     .transport(CassetteTransport::replay("fw.json")?)
+///     .build()?;
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct CassetteTransport {
+    path: PathBuf,
+    mode: Mode,
+    state: Mutex<State>,
+}
+
+impl CassetteTransport {
+    /// Sends requests through `inner` and keeps them, to be written to `path`
+    #[must_use]
+    pub fn record<T: Transport + 'static, P: Into<PathBuf>>(inner: T, path: P) -> Self {
+        Self::recording(Mode::Record(Arc::new(inner)), path.into())
+    }
+
+    /// Async flavour of [`CassetteTransport::record`]
+    #[cfg(feature = "async")]
+    #[must_use]
+    pub fn record_async<T: AsyncTransport + 'static, P: Into<PathBuf>>(inner: T, path: P) -> Self {
+        Self::recording(Mode::RecordAsync(Arc::new(inner)), path.into())
+    }
+
+    fn recording(mode: Mode, path: PathBuf) -> Self {
+        Self {
+            path,
+            mode,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Answers requests with interactions loaded from `path`. Requests not found in the
+    /// cassette fail with [`TransportError::NotRecorded`]
+    pub fn replay<P: Into<PathBuf>>(path: P) -> Result<Self, CassetteError> {
+        let path = path.into();
+        let cassette = Cassette::load(&path)?;
+        let used = vec![false; cassette.interactions.len()];
+        Ok(Self {
+            path,
+            mode: Mode::Replay,
+            state: Mutex::new(State {
+                cassette,
+                used,
+                dirty: false,
+            }),
+        })
+    }
+
+    /// Path of the cassette file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Interactions recorded or loaded so far
+    #[must_use]
+    pub fn cassette(&self) -> Cassette {
+        self.state().cassette.clone()
+    }
+
+    /// Writes recorded interactions to the cassette file. It's also done when a recording
+    /// cassette is dropped, but errors are only logged then
+    pub fn save(&self) -> Result<(), CassetteError> {
+        let mut state = self.state();
+        state.cassette.save(&self.path)?;
+        state.dirty = false;
+        Ok(())
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("cassette lock isn't poisoned")
+    }
+
+    fn keep(&self, request: &HttpRequest, response: &HttpResponse) {
+        let mut state = self.state();
+        state
+            .cassette
+            .interactions
+            .push(Interaction::new(request, response));
+        state.used.push(true);
+        state.dirty = true;
+    }
+
+    fn replayed(&self, request: &HttpRequest) -> Result<HttpResponse, TransportError> {
+        let mut state = self.state();
+        let State { cassette, used, .. } = &mut *state;
+        let mut matching = cassette
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, interaction)| interaction.matches(request));
+        let unused = matching.clone().find(|(i, _)| !used[*i]);
+        // Once every matching interaction has been served, the last one is served again
+        match unused.or_else(|| matching.next_back()) {
+            Some((i, interaction)) => {
+                used[i] = true;
+                Ok(interaction.to_response())
+            }
+            None => Err(TransportError::NotRecorded {
+                method: request.method.as_str(),
+                url: request.url.clone(),
+            }),
+        }
+    }
+}
+
+impl Transport for CassetteTransport {
+    fn execute(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        match &self.mode {
+            Mode::Record(inner) => {
+                let response = inner.execute(request.clone())?;
+                self.keep(&request, &response);
+                Ok(response)
+            }
+            #[cfg(feature = "async")]
+            Mode::RecordAsync(_) => Err(TransportError::NotRecorded {
+                method: request.method.as_str(),
+                url: request.url,
+            }),
+            Mode::Replay => self.replayed(&request),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncTransport for CassetteTransport {
+    fn execute(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, TransportError>> {
+        Box::pin(async move {
+            match &self.mode {
+                Mode::RecordAsync(inner) => {
+                    let response = inner.execute(request.clone()).await?;
+                    self.keep(&request, &response);
+                    Ok(response)
+                }
+                Mode::Record(_) => Err(TransportError::NotRecorded {
+                    method: request.method.as_str(),
+                    url: request.url,
+                }),
+                Mode::Replay => self.replayed(&request),
+            }
+        })
+    }
+}
+
+impl Drop for CassetteTransport {
+    fn drop(&mut self) {
+        if self.state().dirty {
+            if let Err(e) = self.save() {
+                log::error!("Couldn't save the cassette: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::testing::FakeTransport;
+
+    fn cassette_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("filmed-{}-{name}.json", std::process::id()))
+    }
+
+    #[test]
+    fn redacting_set_cookie() {
+        assert_eq!(redact_set_cookie("JWT=abc; Path=/"), "JWT=REDACTED; Path=/");
+        assert_eq!(redact_set_cookie("JWT=abc"), "JWT=REDACTED");
+    }
+
+    #[test]
+    fn redacting_bodies() {
+        assert_eq!(
+            redact_body(br#"{"username":"a","password":"hunter2","rememberMe":true}"#),
+            r#"{"password":"REDACTED","rememberMe":true,"username":"a"}"#
+        );
+        assert_eq!(
+            redact_body(br#"{"data":[{"jwt":"a.b.c"}]}"#),
+            r#"{"data":[{"jwt":"REDACTED"}]}"#
+        );
+        assert_eq!(redact_body(b"\"eyJh.eyJz.c2ln\"\n"), REDACTED_JWT);
+        // Bodies without secrets are kept as they were sent
+        assert_eq!(redact_body(br#"{ "rate": 8 }"#), r#"{ "rate": 8 }"#);
+        assert_eq!(redact_body(b"1.5"), "1.5");
+    }
+
+    #[test]
+    fn recording_and_replaying() {
+        let path = cassette_path("recording");
+        let recorder = CassetteTransport::record(
+            FakeTransport::new(vec![("/first", "1"), ("/second", "2")]),
+            &path,
+        );
+        let request = HttpRequest::get("http://fw.test/first").header("Cookie", "JWT=secret");
+        assert_eq!(Transport::execute(&recorder, request).unwrap().text(), "1");
+        assert_eq!(
+            Transport::get(&recorder, "http://fw.test/second")
+                .unwrap()
+                .text(),
+            "2"
+        );
+        drop(recorder);
+
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(!saved.contains("secret"));
+
+        let player = CassetteTransport::replay(&path).unwrap();
+        assert_eq!(
+            Transport::get(&player, "http://fw.test/second")
+                .unwrap()
+                .text(),
+            "2"
+        );
+        assert_eq!(
+            Transport::get(&player, "http://fw.test/first")
+                .unwrap()
+                .text(),
+            "1"
+        );
+        assert_eq!(
+            Transport::get(&player, "http://fw.test/first")
+                .unwrap()
+                .text(),
+            "1"
+        );
+        assert!(matches!(
+            Transport::get(&player, "http://fw.test/third"),
+            Err(TransportError::NotRecorded { .. })
+        ));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn replaying_repeated_requests_in_order() {
+        let path = cassette_path("repeated");
+        let response = |body: &str| RecordedResponse {
+            status: 200,
+            headers: Vec::new(),
+            body: body.to_owned(),
+        };
+        let request = RecordedRequest {
+            method: Method::Get,
+            url: "http://fw.test/count".to_owned(),
+            headers: Vec::new(),
+            body: None,
+        };
+        Cassette {
+            interactions: vec![
+                Interaction {
+                    request: request.clone(),
+                    response: response("1"),
+                },
+                Interaction {
+                    request,
+                    response: response("2"),
+                },
+            ],
+        }
+        .save(&path)
+        .unwrap();
+
+        let player = CassetteTransport::replay(&path).unwrap();
+        assert_eq!(
+            Transport::get(&player, "http://fw.test/count")
+                .unwrap()
+                .text(),
+            "1"
+        );
+        assert_eq!(
+            Transport::get(&player, "http://fw.test/count")
+                .unwrap()
+                .text(),
+            "2"
+        );
+        assert_eq!(
+            Transport::get(&player, "http://fw.test/count")
+                .unwrap()
+                .text(),
+            "2"
+        );
+        fs::remove_file(path).unwrap();
+    }
+}
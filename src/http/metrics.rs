@@ -0,0 +1,232 @@
+//! Request accounting per [`Endpoint`]: how many requests an export costs, how many bytes they
+//! bring and where the time goes
+
+#[cfg(feature = "async")]
+use super::{AsyncTransport, BoxFuture};
+use super::{Endpoint, HttpRequest, HttpResponse, Transport, TransportError};
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Upper bounds of the latency histogram buckets, in milliseconds. Slower requests land in an
+/// extra, unbounded bucket
+pub const LATENCY_BUCKETS_MS: [u64; 8] = [50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    counts: [u64; LATENCY_BUCKETS_MS.len() + 1],
+    total: Duration,
+}
+
+impl LatencyHistogram {
+    fn record(&mut self, latency: Duration) {
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| latency <= Duration::from_millis(*bound))
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.counts[bucket] += 1;
+        self.total += latency;
+    }
+
+    /// Upper bound of every bucket (`None` for the last one) and how many requests fell in it
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<Duration>, u64)> + '_ {
+        LATENCY_BUCKETS_MS
+            .iter()
+            .map(|bound| Some(Duration::from_millis(*bound)))
+            .chain(std::iter::once(None))
+            .zip(self.counts.iter().copied())
+    }
+
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Time spent waiting for responses
+    #[must_use]
+    pub const fn total(&self) -> Duration {
+        self.total
+    }
+
+    #[must_use]
+    pub fn mean(&self) -> Option<Duration> {
+        let count = u32::try_from(self.count())
+            .ok()
+            .filter(|count| *count > 0)?;
+        Some(self.total / count)
+    }
+}
+
+/// Counters of one endpoint. Requests that failed or got a non-2xx status are errors
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EndpointMetrics {
+    pub requests: u64,
+    pub errors: u64,
+    /// Size of response bodies
+    pub bytes: u64,
+    pub latency: LatencyHistogram,
+}
+
+/// Counters at the time of [`Metrics::snapshot`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    pub endpoints: BTreeMap<Endpoint, EndpointMetrics>,
+}
+
+impl MetricsSnapshot {
+    /// Counters of `endpoint`, zeroed when it wasn't requested
+    #[must_use]
+    pub fn get(&self, endpoint: Endpoint) -> EndpointMetrics {
+        self.endpoints.get(&endpoint).cloned().unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn requests(&self) -> u64 {
+        self.endpoints
+            .values()
+            .map(|metrics| metrics.requests)
+            .sum()
+    }
+
+    #[must_use]
+    pub fn bytes(&self) -> u64 {
+        self.endpoints.values().map(|metrics| metrics.bytes).sum()
+    }
+}
+
+/// Collects [`EndpointMetrics`]. Clones share the counters, so one collector can be given to
+/// several builders to account for a whole export
+#[derive(Clone, Debug, Default)]
+pub struct Metrics {
+    endpoints: Arc<Mutex<BTreeMap<Endpoint, EndpointMetrics>>>,
+}
+
+impl Metrics {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            endpoints: self.lock().clone(),
+        }
+    }
+
+    pub fn reset(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<Endpoint, EndpointMetrics>> {
+        self.endpoints.lock().expect("metrics lock isn't poisoned")
+    }
+
+    fn record(&self, url: &str, latency: Duration, result: &Result<HttpResponse, TransportError>) {
+        let mut endpoints = self.lock();
+        let metrics = endpoints.entry(Endpoint::of(url)).or_default();
+        metrics.requests += 1;
+        metrics.latency.record(latency);
+        match result {
+            Ok(response) => {
+                metrics.bytes += response.body.len() as u64;
+                if !response.is_success() {
+                    metrics.errors += 1;
+                }
+            }
+            Err(_) => metrics.errors += 1,
+        }
+    }
+}
+
+/// Records every request in [`Metrics`]
+#[derive(Debug)]
+pub struct MeteredTransport<T: ?Sized> {
+    metrics: Metrics,
+    inner: Arc<T>,
+}
+
+impl<T: ?Sized> MeteredTransport<T> {
+    pub fn new(inner: Arc<T>, metrics: Metrics) -> Self {
+        Self { metrics, inner }
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for MeteredTransport<T> {
+    fn execute(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        let url = request.url.clone();
+        let start = Instant::now();
+        let result = self.inner.execute(request);
+        self.metrics.record(&url, start.elapsed(), &result);
+        result
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: AsyncTransport + ?Sized> AsyncTransport for MeteredTransport<T> {
+    fn execute(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, TransportError>> {
+        Box::pin(async move {
+            let url = request.url.clone();
+            let start = Instant::now();
+            let result = self.inner.execute(request).await;
+            self.metrics.record(&url, start.elapsed(), &result);
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::testing::FakeTransport;
+
+    #[test]
+    fn counting_requests() {
+        let metrics = Metrics::new();
+        let fake = FakeTransport::new(vec![("/preview", "{}")]).with_status("/details", 500, "");
+        let transport = MeteredTransport::new(Arc::new(fake), metrics.clone());
+        transport
+            .get("http://fw.test/api/v1/film/1/preview")
+            .unwrap();
+        transport
+            .get("http://fw.test/api/v1/film/2/preview")
+            .unwrap();
+        transport
+            .get("http://fw.test/api/v1/logged/vote/film/1/details")
+            .unwrap();
+
+        let snapshot = metrics.snapshot();
+        let previews = snapshot.get(Endpoint::Preview);
+        assert_eq!(
+            (previews.requests, previews.errors, previews.bytes),
+            (2, 0, 4)
+        );
+        assert_eq!(previews.latency.count(), 2);
+        assert_eq!(snapshot.get(Endpoint::VoteDetails).errors, 1);
+        assert_eq!(
+            snapshot.get(Endpoint::IMDbSearch),
+            EndpointMetrics::default()
+        );
+        assert_eq!(snapshot.requests(), 3);
+
+        metrics.reset();
+        assert_eq!(metrics.snapshot().requests(), 0);
+    }
+
+    #[test]
+    fn bucketing_latencies() {
+        let mut histogram = LatencyHistogram::default();
+        histogram.record(Duration::from_millis(10));
+        histogram.record(Duration::from_millis(100));
+        histogram.record(Duration::from_secs(60));
+        let buckets: Vec<_> = histogram.buckets().collect();
+        assert_eq!(buckets[0], (Some(Duration::from_millis(50)), 1));
+        assert_eq!(buckets[1], (Some(Duration::from_millis(100)), 1));
+        assert_eq!(buckets[8], (None, 1));
+        assert_eq!(
+            histogram.mean(),
+            Some(Duration::from_nanos(60_110_000_000 / 3))
+        );
+    }
+}
@@ -0,0 +1,312 @@
+//! Pool of transports, e.g. clients going through different proxies or source addresses.
+//! Members that keep failing are benched for a while and the rest take over their requests.
+
+#[cfg(feature = "async")]
+use super::{AsyncReqwestTransport, AsyncTransport, BoxFuture};
+use super::{
+    ClientOptions, HttpRequest, HttpResponse, ReqwestTransport, Transport, TransportError,
+};
+use crate::error::BuildError;
+
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How a [`ClientPool`] picks a member for the next request
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Selection {
+    #[default]
+    RoundRobin,
+    /// The member with the fewest requests in flight
+    LeastLoaded,
+}
+
+#[derive(Debug, Default)]
+struct Health {
+    consecutive_failures: u32,
+    benched_until: Option<Instant>,
+}
+
+#[derive(Debug)]
+struct Member<T: ?Sized> {
+    transport: Arc<T>,
+    in_flight: AtomicUsize,
+    health: Mutex<Health>,
+}
+
+impl<T: ?Sized> Member<T> {
+    fn health(&self) -> std::sync::MutexGuard<'_, Health> {
+        self.health.lock().expect("pool lock isn't poisoned")
+    }
+
+    fn benched_until(&self, now: Instant) -> Option<Instant> {
+        self.health().benched_until.filter(|until| *until > now)
+    }
+}
+
+/// Counts a request as in flight until dropped
+struct InFlight<'a>(&'a AtomicUsize);
+
+impl<'a> InFlight<'a> {
+    fn new(counter: &'a AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Self(counter)
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Spreads requests over its members. A member that fails `failure_threshold` times in a row
+/// (a connection error, a timeout, `429` or a 5xx) sits out the `bench` duration. When every
+/// member is benched, the one coming back first is used anyway
+///
+/// # Examples
+/// ```rust,no_run
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use filmed::filmweb::FilmwebUser;
+/// use filmed::http::{ClientOptions, ClientPool, Selection};
+///
+/// let pool = ClientPool::from_options(&[
+///     ClientOptions::new(),
+///     ClientOptions::new().proxy("http://10.0.0.1:3128"),
+/// ])?
+/// .selection(Selection::LeastLoaded);
+/// let user = FilmwebUser::builder()
+///     .transport(pool)
+///     .build("token", "session", "jwt")?;
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ClientPool<T: ?Sized = dyn Transport> {
+    members: Vec<Member<T>>,
+    selection: Selection,
+    next: AtomicUsize,
+    failure_threshold: u32,
+    bench: Duration,
+}
+
+impl<T: ?Sized> ClientPool<T> {
+    /// # Panics
+    /// When `transports` is empty
+    #[must_use]
+    pub fn new(transports: Vec<Arc<T>>) -> Self {
+        assert!(!transports.is_empty(), "a pool needs at least one member");
+        log::debug!("Creating a ClientPool of {}", transports.len());
+        Self {
+            members: transports
+                .into_iter()
+                .map(|transport| Member {
+                    transport,
+                    in_flight: AtomicUsize::new(0),
+                    health: Mutex::default(),
+                })
+                .collect(),
+            selection: Selection::default(),
+            next: AtomicUsize::new(0),
+            failure_threshold: 3,
+            bench: Duration::from_secs(60),
+        }
+    }
+
+    #[must_use]
+    pub const fn selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// How many failures in a row bench a member, 3 by default
+    ///
+    /// # Panics
+    /// When `failures` is zero
+    #[must_use]
+    pub fn failure_threshold(mut self, failures: u32) -> Self {
+        assert!(
+            failures > 0,
+            "a member is benched after at least one failure"
+        );
+        self.failure_threshold = failures;
+        self
+    }
+
+    /// How long a failing member is out of rotation, a minute by default
+    #[must_use]
+    pub const fn bench(mut self, bench: Duration) -> Self {
+        self.bench = bench;
+        self
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Members currently out of rotation
+    #[must_use]
+    pub fn benched(&self) -> usize {
+        let now = Instant::now();
+        self.members
+            .iter()
+            .filter(|member| member.benched_until(now).is_some())
+            .count()
+    }
+
+    fn pick(&self) -> &Member<T> {
+        let now = Instant::now();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let len = self.members.len();
+        let available = (0..len)
+            .map(|offset| &self.members[(start + offset) % len])
+            .filter(|member| member.benched_until(now).is_none());
+        let picked = match self.selection {
+            Selection::RoundRobin => available.into_iter().next(),
+            Selection::LeastLoaded => {
+                available.min_by_key(|member| member.in_flight.load(Ordering::SeqCst))
+            }
+        };
+        picked.unwrap_or_else(|| {
+            self.members
+                .iter()
+                .min_by_key(|member| member.benched_until(now))
+                .expect("pool isn't empty")
+        })
+    }
+
+    fn record(&self, member: &Member<T>, result: &Result<HttpResponse, TransportError>) {
+        let failed = match result {
+            Ok(response) => response.status == 429 || response.status >= 500,
+            Err(_) => true,
+        };
+        let mut health = member.health();
+        if !failed {
+            health.consecutive_failures = 0;
+            return;
+        }
+        health.consecutive_failures += 1;
+        if health.consecutive_failures >= self.failure_threshold {
+            log::warn!("Benching a pool member for {:?}", self.bench);
+            health.consecutive_failures = 0;
+            health.benched_until = Some(Instant::now() + self.bench);
+        }
+    }
+}
+
+impl ClientPool<dyn Transport> {
+    /// A [`ReqwestTransport`] for each of `options`, so every member has its own connections
+    pub fn from_options(options: &[ClientOptions]) -> Result<Self, BuildError> {
+        let transports = options
+            .iter()
+            .map(|options| Ok(Arc::new(ReqwestTransport::with_options(options)?) as Arc<_>))
+            .collect::<Result<_, BuildError>>()?;
+        Ok(Self::new(transports))
+    }
+}
+
+#[cfg(feature = "async")]
+impl ClientPool<dyn AsyncTransport> {
+    /// Async flavour of [`ClientPool::from_options`]
+    pub fn from_options_async(options: &[ClientOptions]) -> Result<Self, BuildError> {
+        let transports = options
+            .iter()
+            .map(|options| Ok(Arc::new(AsyncReqwestTransport::with_options(options)?) as Arc<_>))
+            .collect::<Result<_, BuildError>>()?;
+        Ok(Self::new(transports))
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for ClientPool<T> {
+    fn execute(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        let member = self.pick();
+        let result = {
+            let _in_flight = InFlight::new(&member.in_flight);
+            member.transport.execute(request)
+        };
+        self.record(member, &result);
+        result
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: AsyncTransport + ?Sized> AsyncTransport for ClientPool<T> {
+    fn execute(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, TransportError>> {
+        Box::pin(async move {
+            let member = self.pick();
+            let result = {
+                let _in_flight = InFlight::new(&member.in_flight);
+                member.transport.execute(request).await
+            };
+            self.record(member, &result);
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::testing::FakeTransport;
+
+    fn member(status: u16) -> Arc<FakeTransport> {
+        Arc::new(FakeTransport::new(Vec::new()).with_status("/", status, ""))
+    }
+
+    fn calls(transport: &FakeTransport) -> usize {
+        transport.requests.lock().unwrap().len()
+    }
+
+    #[test]
+    fn rotating_members() {
+        let (first, second) = (member(200), member(200));
+        let pool = ClientPool::new(vec![Arc::clone(&first), Arc::clone(&second)]);
+        for _ in 0..4 {
+            pool.get("http://fw.test/").unwrap();
+        }
+        assert_eq!((calls(&first), calls(&second)), (2, 2));
+    }
+
+    #[test]
+    fn benching_failing_members() {
+        let (failing, healthy) = (member(503), member(200));
+        let pool = ClientPool::new(vec![Arc::clone(&failing), Arc::clone(&healthy)])
+            .failure_threshold(2)
+            .bench(Duration::from_secs(60));
+        for _ in 0..10 {
+            pool.get("http://fw.test/").unwrap();
+        }
+        assert_eq!(calls(&failing), 2);
+        assert_eq!(calls(&healthy), 8);
+        assert_eq!(pool.benched(), 1);
+
+        // With everyone benched, requests still go somewhere
+        let pool = ClientPool::new(vec![member(503)]).failure_threshold(1);
+        pool.get("http://fw.test/").unwrap();
+        assert_eq!(pool.benched(), 1);
+        assert_eq!(pool.get("http://fw.test/").unwrap().status, 503);
+    }
+
+    #[test]
+    fn picking_least_loaded_member() {
+        let pool = ClientPool::new(vec![member(200), member(200), member(200)])
+            .selection(Selection::LeastLoaded);
+        let _busy = InFlight::new(&pool.members[0].in_flight);
+        let _busier = (
+            InFlight::new(&pool.members[1].in_flight),
+            InFlight::new(&pool.members[1].in_flight),
+        );
+        for _ in 0..3 {
+            assert!(std::ptr::eq(pool.pick(), &pool.members[2]));
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! Token-bucket rate limiting, so scraping a big library doesn't get us blocked
+
+#[cfg(feature = "async")]
+use super::{AsyncTransport, BoxFuture};
+use super::{HttpRequest, HttpResponse, Transport, TransportError};
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Requests per second and burst used unless a [`RateLimiter`] is given to a builder
+pub const DEFAULT_REQUESTS_PER_SECOND: f64 = 5.0;
+pub const DEFAULT_BURST: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Limit {
+    requests_per_second: f64,
+    burst: f64,
+}
+
+impl Limit {
+    fn new(requests_per_second: f64, burst: u32) -> Self {
+        assert!(
+            requests_per_second > 0.0 && burst > 0,
+            "rate limit must allow some requests"
+        );
+        Self {
+            requests_per_second,
+            burst: f64::from(burst),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    limit: Limit,
+    /// Goes below zero when requests are waiting for their turn
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    fn new(limit: Limit, now: Instant) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst,
+            refilled_at: now,
+        }
+    }
+
+    fn reserve(&mut self, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        self.tokens = self
+            .limit
+            .burst
+            .min(self.tokens + elapsed.as_secs_f64() * self.limit.requests_per_second);
+        self.refilled_at = now;
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.limit.requests_per_second)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Buckets {
+    default: Limit,
+    per_host: HashMap<String, Limit>,
+    buckets: HashMap<String, Bucket>,
+}
+
+/// A token bucket per host. Clones share the buckets, so one limiter can be given to every
+/// scraper, e.g. to a [`FilmwebUser`](crate::filmweb::FilmwebUser) and an
+/// [`IMDb`](crate::imdb::IMDb)
+///
+/// # Examples
+/// ```rust,no_run
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use filmed::filmweb::Filmweb;
+/// use filmed::http::RateLimiter;
+/// use filmed::imdb::IMDb;
+///
+/// let limiter = RateLimiter::new(2.0, 4).host("www.imdb.com", 1.0, 2);
+/// let fw = Filmweb::builder().rate_limiter(limiter.clone()).build()?;
+/// let imdb = IMDb::builder().rate_limiter(limiter).build()?;
+/// #     Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    buckets: Arc<Mutex<Buckets>>,
+}
+
+impl RateLimiter {
+    /// Allows `requests_per_second` to every host, with up to `burst` requests sent at once
+    ///
+    /// # Panics
+    /// When either of them is zero
+    #[must_use]
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        Self {
+            buckets: Arc::new(Mutex::new(Buckets {
+                default: Limit::new(requests_per_second, burst),
+                per_host: HashMap::new(),
+                buckets: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Uses a different limit for `host`
+    ///
+    /// # Panics
+    /// When either `requests_per_second` or `burst` is zero
+    #[must_use]
+    pub fn host<T: Into<String>>(self, host: T, requests_per_second: f64, burst: u32) -> Self {
+        let host = host.into();
+        let limit = Limit::new(requests_per_second, burst);
+        {
+            let mut buckets = self.lock();
+            buckets.buckets.remove(&host);
+            buckets.per_host.insert(host, limit);
+        }
+        self
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Buckets> {
+        self.buckets
+            .lock()
+            .expect("rate limiter lock isn't poisoned")
+    }
+
+    /// Takes a token from the bucket of `url`'s host and returns how long to wait before
+    /// sending the request
+    #[must_use]
+    pub fn reserve(&self, url: &str) -> Duration {
+        let host = host(url);
+        let now = Instant::now();
+        let mut buckets = self.lock();
+        let limit = buckets
+            .per_host
+            .get(&host)
+            .copied()
+            .unwrap_or(buckets.default);
+        buckets
+            .buckets
+            .entry(host)
+            .or_insert_with(|| Bucket::new(limit, now))
+            .reserve(now)
+    }
+
+    /// Blocks until a request to `url` can be sent
+    pub fn wait(&self, url: &str) {
+        let delay = self.reserve(url);
+        if !delay.is_zero() {
+            log::debug!("Rate limited, waiting {delay:?} before requesting {url}");
+            thread::sleep(delay);
+        }
+    }
+
+    /// Async flavour of [`RateLimiter::wait`]
+    #[cfg(feature = "async")]
+    pub async fn wait_async(&self, url: &str) {
+        let delay = self.reserve(url);
+        if !delay.is_zero() {
+            log::debug!("Rate limited, waiting {delay:?} before requesting {url}");
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(DEFAULT_REQUESTS_PER_SECOND, DEFAULT_BURST)
+    }
+}
+
+/// `host[:port]` of a url, or the whole url if it can't be parsed
+fn host(url: &str) -> String {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|url| {
+            url.host_str().map(|host| match url.port() {
+                Some(port) => format!("{host}:{port}"),
+                None => host.to_owned(),
+            })
+        })
+        .unwrap_or_else(|| url.to_owned())
+}
+
+/// Waits for the [`RateLimiter`] before every request
+#[derive(Debug)]
+pub struct RateLimitedTransport<T: ?Sized> {
+    limiter: RateLimiter,
+    inner: Arc<T>,
+}
+
+impl<T: ?Sized> RateLimitedTransport<T> {
+    pub fn new(inner: Arc<T>, limiter: RateLimiter) -> Self {
+        Self { limiter, inner }
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for RateLimitedTransport<T> {
+    fn execute(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        self.limiter.wait(&request.url);
+        self.inner.execute(request)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: AsyncTransport + ?Sized> AsyncTransport for RateLimitedTransport<T> {
+    fn execute(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, TransportError>> {
+        Box::pin(async move {
+            self.limiter.wait_async(&request.url).await;
+            self.inner.execute(request).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserving_tokens() {
+        let limiter = RateLimiter::new(1.0, 2);
+        assert!(limiter.reserve("https://www.filmweb.pl/a").is_zero());
+        assert!(limiter.reserve("https://www.filmweb.pl/b").is_zero());
+        // The third request waits for a token, the fourth for the one after it
+        let third = limiter.reserve("https://www.filmweb.pl/c");
+        let fourth = limiter.clone().reserve("https://www.filmweb.pl/d");
+        assert!(third > Duration::from_millis(900));
+        assert!(fourth > Duration::from_millis(1900));
+        // Other hosts have their own buckets
+        assert!(limiter.reserve("https://www.imdb.com/find").is_zero());
+    }
+
+    #[test]
+    fn limiting_hosts_differently() {
+        let limiter = RateLimiter::new(1.0, 1).host("www.imdb.com", 1.0, 3);
+        assert!(limiter.reserve("https://www.filmweb.pl").is_zero());
+        assert!(!limiter.reserve("https://www.filmweb.pl").is_zero());
+        for _ in 0..3 {
+            assert!(limiter.reserve("https://www.imdb.com").is_zero());
+        }
+        assert_eq!(host("http://127.0.0.1:8080/settings"), "127.0.0.1:8080");
+    }
+}
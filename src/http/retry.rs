@@ -0,0 +1,382 @@
+//! Retrying of idempotent requests that failed for a transient reason: a dropped connection,
+//! one reset or closed mid-response, a timeout, `429 Too Many Requests` or a 5xx gateway/server
+//! error
+
+#[cfg(feature = "async")]
+use super::{AsyncTransport, BoxFuture};
+use super::{HttpRequest, HttpResponse, Method, Transport, TransportError};
+
+use std::error::Error;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use time::format_description::well_known::Rfc2822;
+use time::OffsetDateTime;
+
+/// Statuses worth another attempt
+const RETRYABLE_STATUSES: [u16; 5] = [429, 500, 502, 503, 504];
+
+/// How many times and how long apart a GET request is attempted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// # Panics
+    /// When `max_attempts` is zero
+    #[must_use]
+    pub fn new(max_attempts: u32) -> Self {
+        assert!(max_attempts > 0, "a request is attempted at least once");
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before the second attempt, doubled before each next one
+    #[must_use]
+    pub const fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Upper bound of a delay, also of the one asked for with `Retry-After`
+    #[must_use]
+    pub const fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    #[must_use]
+    pub const fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Exponential backoff after `attempt` failed attempts, without jitter
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(attempt.saturating_sub(1));
+        self.base_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+
+    /// How long to wait after `attempt` failed attempts. `Retry-After` wins over backoff
+    fn delay(&self, attempt: u32, response: Option<&HttpResponse>) -> Duration {
+        match response.and_then(retry_after) {
+            Some(delay) => delay.min(self.max_delay),
+            // Up to a fifth less, so clients that failed together don't retry together
+            None => self.backoff(attempt).mul_f64(1.0 - fastrand::f64() / 5.0),
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+/// `Retry-After` given in seconds, or as an HTTP-date, which is waited for from now on
+fn retry_after(response: &HttpResponse) -> Option<Duration> {
+    let value = response.header("retry-after")?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = OffsetDateTime::parse(value, &Rfc2822).ok()?;
+    let delay = date - OffsetDateTime::now_utc();
+    Some(delay.try_into().unwrap_or_default())
+}
+
+fn is_retryable_error(error: &TransportError) -> bool {
+    match error {
+        TransportError::Connect { .. } | TransportError::Timeout { .. } => true,
+        TransportError::Request { source, .. } => is_interrupted(source.as_ref()),
+        _ => false,
+    }
+}
+
+/// Whether a request failed midway, e.g. the connection was reset or closed while the response
+/// was read. A request that couldn't be built or redirected fails the same way every time
+fn is_interrupted(source: &(dyn Error + 'static)) -> bool {
+    if let Some(error) = source.downcast_ref::<reqwest::Error>() {
+        return (error.is_request() || error.is_body() || error.is_decode())
+            && !error.is_builder()
+            && !error.is_redirect();
+    }
+    // Other transports are trusted to tell an I/O error by its source
+    std::iter::successors(Some(source), |&error| error.source())
+        .any(|error| error.is::<std::io::Error>())
+}
+
+/// Outcome of one attempt
+enum Attempt {
+    Done(Result<HttpResponse, TransportError>),
+    Retry(Result<HttpResponse, TransportError>),
+}
+
+impl Attempt {
+    fn new(request: &HttpRequest, result: Result<HttpResponse, TransportError>) -> Self {
+        let retryable = request.method == Method::Get
+            && match &result {
+                Ok(response) => RETRYABLE_STATUSES.contains(&response.status),
+                Err(e) => is_retryable_error(e),
+            };
+        if retryable {
+            Self::Retry(result)
+        } else {
+            Self::Done(result)
+        }
+    }
+}
+
+/// Turns the last failure into an error telling how many attempts were made
+fn give_up(
+    url: &str,
+    attempts: u32,
+    result: Result<HttpResponse, TransportError>,
+) -> TransportError {
+    match result {
+        Ok(response) => TransportError::Status {
+            url: url.to_owned(),
+            status: response.status,
+            attempts,
+        },
+        Err(e) => TransportError::RetriesExhausted {
+            attempts,
+            source: Box::new(e),
+        },
+    }
+}
+
+/// Retries GET requests according to a [`RetryPolicy`]
+#[derive(Debug)]
+pub struct RetryTransport<T: ?Sized> {
+    policy: RetryPolicy,
+    inner: Arc<T>,
+}
+
+impl<T: ?Sized> RetryTransport<T> {
+    pub fn new(inner: Arc<T>, policy: RetryPolicy) -> Self {
+        Self { policy, inner }
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for RetryTransport<T> {
+    fn execute(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+        let mut attempt = 1;
+        loop {
+            let result = match Attempt::new(&request, self.inner.execute(request.clone())) {
+                Attempt::Done(result) => return result,
+                Attempt::Retry(result) => result,
+            };
+            if attempt == self.policy.max_attempts {
+                return Err(give_up(&request.url, attempt, result));
+            }
+            let delay = self.policy.delay(attempt, result.as_ref().ok());
+            log::info!(
+                "Attempt {attempt} of {} failed, retrying in {delay:?}",
+                request.url
+            );
+            thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: AsyncTransport + ?Sized> AsyncTransport for RetryTransport<T> {
+    fn execute(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, TransportError>> {
+        Box::pin(async move {
+            let mut attempt = 1;
+            loop {
+                let result = self.inner.execute(request.clone()).await;
+                let result = match Attempt::new(&request, result) {
+                    Attempt::Done(result) => return result,
+                    Attempt::Retry(result) => result,
+                };
+                if attempt == self.policy.max_attempts {
+                    return Err(give_up(&request.url, attempt, result));
+                }
+                let delay = self.policy.delay(attempt, result.as_ref().ok());
+                log::info!(
+                    "Attempt {attempt} of {} failed, retrying in {delay:?}",
+                    request.url
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Fails the first `failures` requests with `status`, with a dropped connection when it's
+    /// zero, or with a connection reset mid-response when it's one
+    #[derive(Debug)]
+    struct Flaky {
+        failures: u32,
+        status: u16,
+        retry_after: Option<&'static str>,
+        calls: AtomicU32,
+    }
+
+    impl Flaky {
+        fn new(failures: u32, status: u16) -> Arc<Self> {
+            Arc::new(Self {
+                failures,
+                status,
+                retry_after: None,
+                calls: AtomicU32::new(0),
+            })
+        }
+    }
+
+    impl Transport for Flaky {
+        fn execute(&self, request: HttpRequest) -> Result<HttpResponse, TransportError> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            if call >= self.failures {
+                return Ok(HttpResponse {
+                    status: 200,
+                    headers: Vec::new(),
+                    body: "ok".into(),
+                });
+            }
+            if self.status == 0 {
+                return Err(TransportError::Connect {
+                    url: request.url,
+                    source: "connection refused".into(),
+                });
+            }
+            if self.status == 1 {
+                return Err(TransportError::Request {
+                    url: request.url,
+                    source: std::io::Error::from(std::io::ErrorKind::ConnectionReset).into(),
+                });
+            }
+            Ok(HttpResponse {
+                status: self.status,
+                headers: self
+                    .retry_after
+                    .map(|value| vec![("Retry-After".to_owned(), value.to_owned())])
+                    .unwrap_or_default(),
+                body: Vec::new(),
+            })
+        }
+    }
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy::new(max_attempts).base_delay(Duration::from_millis(1))
+    }
+
+    #[test]
+    fn retrying_transient_failures() {
+        let flaky = Flaky::new(2, 503);
+        let transport = RetryTransport::new(Arc::clone(&flaky), policy(3));
+        assert_eq!(transport.get("http://fw.test").unwrap().text(), "ok");
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+
+        for dropped in [0, 1] {
+            let flaky = Flaky::new(1, dropped);
+            let transport = RetryTransport::new(Arc::clone(&flaky), policy(3));
+            assert!(transport.get("http://fw.test").is_ok());
+            assert_eq!(flaky.calls.load(Ordering::SeqCst), 2);
+        }
+    }
+
+    #[test]
+    fn telling_interrupted_requests() {
+        let error = |source: Box<dyn Error + Send + Sync>| TransportError::Request {
+            url: "http://fw.test".to_owned(),
+            source,
+        };
+        let reset = std::io::Error::from(std::io::ErrorKind::UnexpectedEof);
+        assert!(is_retryable_error(&error(reset.into())));
+        assert!(!is_retryable_error(&error("invalid header value".into())));
+        let builder_error = reqwest::blocking::Client::new()
+            .get("not a url")
+            .build()
+            .unwrap_err();
+        assert!(!is_retryable_error(&error(builder_error.into())));
+    }
+
+    #[test]
+    fn reporting_attempts() {
+        let transport = RetryTransport::new(Flaky::new(5, 429), policy(3));
+        assert!(matches!(
+            transport.get("http://fw.test"),
+            Err(TransportError::Status {
+                status: 429,
+                attempts: 3,
+                ..
+            })
+        ));
+
+        let transport = RetryTransport::new(Flaky::new(5, 0), policy(2));
+        assert!(matches!(
+            transport.get("http://fw.test"),
+            Err(TransportError::RetriesExhausted { attempts: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn not_retrying_other_requests() {
+        let flaky = Flaky::new(1, 404);
+        let transport = RetryTransport::new(Arc::clone(&flaky), policy(3));
+        assert_eq!(transport.get("http://fw.test").unwrap().status, 404);
+
+        let flaky = Flaky::new(1, 503);
+        let transport = RetryTransport::new(Arc::clone(&flaky), policy(3));
+        let response = transport
+            .execute(HttpRequest::post("http://fw.test", Vec::new()))
+            .unwrap();
+        assert_eq!(response.status, 503);
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn computing_delays() {
+        let policy = RetryPolicy::default()
+            .base_delay(Duration::from_secs(1))
+            .max_delay(Duration::from_secs(5));
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(10), Duration::from_secs(5));
+
+        let response = |retry_after: &str| HttpResponse {
+            status: 429,
+            headers: vec![("retry-after".to_owned(), retry_after.to_owned())],
+            body: Vec::new(),
+        };
+        assert_eq!(
+            policy.delay(1, Some(&response("2"))),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            policy.delay(1, Some(&response("120"))),
+            Duration::from_secs(5)
+        );
+        // A date in the past means retrying right away, one in the future is waited for
+        assert_eq!(
+            policy.delay(1, Some(&response("Wed, 21 Oct 2015 07:28:00 GMT"))),
+            Duration::ZERO
+        );
+        let soon = (OffsetDateTime::now_utc() + Duration::from_secs(4))
+            .format(&Rfc2822)
+            .unwrap();
+        let delay = policy.delay(1, Some(&response(&soon)));
+        assert!(delay > Duration::from_secs(2) && delay <= Duration::from_secs(4));
+        assert!(policy.delay(1, Some(&response("soon"))) <= Duration::from_secs(1));
+    }
+}
@@ -1,9 +1,16 @@
-use crate::{error::IMDbScrapeError, utils::create_client, Genre, Title, TitleID, TitleType, Year};
+#[cfg(feature = "async")]
+use crate::http::AsyncTransport;
+use crate::{
+    error::{BuildError, IMDbScrapeError},
+    http::{transport_setters, Metrics, MetricsSnapshot, Transport, TransportOptions},
+    utils::parse_base_url,
+    Genre, Title, TitleID, TitleType, Year,
+};
 use std::str::FromStr;
+use std::sync::Arc;
 
 use once_cell::sync::OnceCell;
 use regex::Regex;
-use reqwest::blocking::Client;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
 
@@ -20,9 +27,11 @@ pub struct IMDbTitle {
 }
 
 impl Title for IMDbTitle {
+    /// Title page on the origin the title was scraped from, or on <https://www.imdb.com> when
+    /// the title was deserialized
     fn url(&self) -> &String {
-        if self.url.get().is_none() {}
-        todo!()
+        self.url
+            .get_or_init(|| title_page_url(IMDB_BASE_URL, &self.id.to_string()))
     }
 
     fn duration(&self) -> Option<u16> {
@@ -50,7 +59,15 @@ impl Title for IMDbTitle {
     }
 }
 
-pub struct IMDb(Client);
+/// Origin of every `IMDb` request, unless changed with an [`IMDbBuilder`]
+pub const IMDB_BASE_URL: &str = "https://www.imdb.com";
+
+#[derive(Debug)]
+pub struct IMDb {
+    transport: Arc<dyn Transport>,
+    base_url: String,
+    metrics: Metrics,
+}
 
 impl Default for IMDb {
     fn default() -> Self {
@@ -62,7 +79,7 @@ impl IMDb {
     /// Returns a queryable `IMDb` struct
     ///
     /// # Examples
-    /// ```rust
+    /// ```rust,no_run
     /// # use std::error::Error;
     /// #
     /// # fn main() -> Result<(), Box<dyn Error>> {
@@ -76,82 +93,309 @@ impl IMDb {
     /// ```
     #[must_use]
     pub fn new() -> Self {
-        Self(create_client().expect("can create a client"))
+        Self::builder().build().expect("can create a client")
     }
 
-    fn parse_imdb_title_page(&self, id: &str) -> Result<ScrapedIMDbTitlePageData, IMDbScrapeError> {
-        let title_url = format!("https://www.imdb.com/title/{id}/");
-        let response = self.0.get(&title_url).send()?.text()?;
-        let dom = tl::parse(&response, tl::ParserOptions::default()).unwrap();
-        let parser = dom.parser();
-        let genres: Vec<Genre> = {
-            dom.query_selector(".ipc-chip__text")
-                .unwrap()
-                .filter_map(|handle| {
-                    let node = handle.get(parser).unwrap();
-                    Genre::try_from(&*node.inner_text(parser)).ok()
-                })
-                .collect()
-        };
+    /// Returns a builder to configure `IMDb`, e.g. to query a mock server or a proxy
+    #[must_use]
+    pub fn builder() -> IMDbBuilder {
+        IMDbBuilder::new()
+    }
 
-        if genres.is_empty() {
-            return Err(IMDbScrapeError::GenreParseError {
-                bad_title_url: title_url,
-            });
-        }
+    /// Origin that all requests are sent to
+    #[must_use]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
 
-        let get_dirty_duration = |nth| {
-            let handle = dom
-                .query_selector(".ipc-inline-list__item")
-                .unwrap()
-                .nth(nth)
-                .expect("Panic occured while trying to export {title} {year}");
-            handle.get(parser).unwrap().inner_text(parser)
-        };
+    /// Requests sent so far, per endpoint
+    #[must_use]
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
 
-        let mut x = 4;
-        let dirty_duration = {
-            loop {
-                let duration_candidate = get_dirty_duration(x);
-                if Self::is_dirty_duration_ok(&duration_candidate) {
-                    break duration_candidate;
-                };
-                if x == 7 {
-                    return Err(IMDbScrapeError::IrrecoverableParseDurationError {
-                        bad_string: duration_candidate.to_string(),
-                        title_url: title_url.to_string(),
-                    });
-                }
-                x += 1;
-            }
-        };
+    fn fetch_imdb_title_page(&self, id: &str) -> Result<ScrapedIMDbTitlePageData, IMDbScrapeError> {
+        let title_url = title_page_url(&self.base_url, id);
+        let response = self.transport.get(&title_url)?.text();
+        parse_imdb_title_page(&response, title_url)
+    }
 
-        let duration = Self::parse_dirty_duration(&dirty_duration, &title_url)?;
-
-        let title_type = {
-            let page_title = {
-                dom.query_selector("title")
-                    .unwrap()
-                    .next()
-                    .unwrap()
-                    .get(parser)
-                    .unwrap()
-                    .inner_text(parser)
-            };
-            if page_title.contains("TV") && page_title.contains("Series") {
-                TitleType::Show
-            } else {
-                TitleType::Movie
-            }
-        };
+    pub fn advanced_search(
+        &self,
+        title: &str,
+        year_start: u16,
+        year_end: u16,
+    ) -> Result<IMDbTitle, IMDbScrapeError> {
+        let search_page_url = advanced_search_url(&self.base_url, title, year_start, year_end);
+        let response = self.transport.get(&search_page_url)?.text();
+        let search_result =
+            parse_advanced_search_page(&response, title, year_start, search_page_url)?;
+        let title_page = self.fetch_imdb_title_page(&search_result.id)?;
+        Ok(IMDbTitle::from_scraped(
+            &self.base_url,
+            search_result,
+            title_page,
+        ))
+    }
+
+    pub fn search(&self, title: &str) -> Result<IMDbTitle, IMDbScrapeError> {
+        let url_query = search_url(&self.base_url, title);
+        let response = self.transport.get(&url_query)?.text();
+        let search_result = parse_search_page(&response, url_query)?;
+        let title_page = self.fetch_imdb_title_page(&search_result.id)?;
+        Ok(IMDbTitle::from_scraped(
+            &self.base_url,
+            search_result,
+            title_page,
+        ))
+    }
+}
+
+/// Async flavour of [`IMDb`], sharing its parsing code
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct AsyncIMDb {
+    transport: Arc<dyn AsyncTransport>,
+    base_url: String,
+    metrics: Metrics,
+}
+
+#[cfg(feature = "async")]
+impl Default for AsyncIMDb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncIMDb {
+    /// Returns a queryable `AsyncIMDb` struct
+    #[must_use]
+    pub fn new() -> Self {
+        IMDbBuilder::new()
+            .build_async()
+            .expect("can create a client")
+    }
+
+    /// Origin that all requests are sent to
+    #[must_use]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Requests sent so far, per endpoint
+    #[must_use]
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    async fn fetch_imdb_title_page(
+        &self,
+        id: &str,
+    ) -> Result<ScrapedIMDbTitlePageData, IMDbScrapeError> {
+        let title_url = title_page_url(&self.base_url, id);
+        let response = self.transport.get(&title_url).await?.text();
+        parse_imdb_title_page(&response, title_url)
+    }
 
-        Ok(ScrapedIMDbTitlePageData {
+    /// See [`IMDb::advanced_search`]
+    pub async fn advanced_search(
+        &self,
+        title: &str,
+        year_start: u16,
+        year_end: u16,
+    ) -> Result<IMDbTitle, IMDbScrapeError> {
+        let search_page_url = advanced_search_url(&self.base_url, title, year_start, year_end);
+        let response = self.transport.get(&search_page_url).await?.text();
+        let search_result =
+            parse_advanced_search_page(&response, title, year_start, search_page_url)?;
+        let title_page = self.fetch_imdb_title_page(&search_result.id).await?;
+        Ok(IMDbTitle::from_scraped(
+            &self.base_url,
+            search_result,
+            title_page,
+        ))
+    }
+
+    /// See [`IMDb::search`]
+    pub async fn search(&self, title: &str) -> Result<IMDbTitle, IMDbScrapeError> {
+        let url_query = search_url(&self.base_url, title);
+        let response = self.transport.get(&url_query).await?.text();
+        let search_result = parse_search_page(&response, url_query)?;
+        let title_page = self.fetch_imdb_title_page(&search_result.id).await?;
+        Ok(IMDbTitle::from_scraped(
+            &self.base_url,
+            search_result,
+            title_page,
+        ))
+    }
+}
+
+impl IMDbTitle {
+    fn from_scraped(
+        base_url: &str,
+        search_result: ScrapedIMDbSearchResult,
+        title_page: ScrapedIMDbTitlePageData,
+    ) -> Self {
+        let ScrapedIMDbSearchResult { id, title, year } = search_result;
+        let ScrapedIMDbTitlePageData {
             genres,
             duration,
             title_type,
+        } = title_page;
+        Self {
+            url: OnceCell::with_value(title_page_url(base_url, &id)),
+            id: TitleID::IMDbID(id),
+            year,
+            genres,
+            title,
+            duration,
+            title_type,
+        }
+    }
+}
+
+/// Builder of [`IMDb`] (and `AsyncIMDb`)
+#[derive(Debug, Clone)]
+pub struct IMDbBuilder {
+    base_url: String,
+    options: TransportOptions,
+}
+
+impl IMDbBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            base_url: IMDB_BASE_URL.to_owned(),
+            options: TransportOptions::new(),
+        }
+    }
+
+    /// Sends every request to a different origin than <https://www.imdb.com>
+    #[must_use]
+    pub fn base_url<T: Into<String>>(mut self, base_url: T) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    transport_setters!();
+
+    pub fn build(self) -> Result<IMDb, BuildError> {
+        Ok(IMDb {
+            base_url: parse_base_url(&self.base_url)?,
+            metrics: self.options.metrics.clone(),
+            transport: self.options.build()?,
+        })
+    }
+
+    #[cfg(feature = "async")]
+    pub fn build_async(self) -> Result<AsyncIMDb, BuildError> {
+        Ok(AsyncIMDb {
+            base_url: parse_base_url(&self.base_url)?,
+            metrics: self.options.metrics.clone(),
+            transport: self.options.build_async()?,
         })
     }
+}
+
+impl Default for IMDbBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn title_page_url(base_url: &str, id: &str) -> String {
+    format!("{base_url}/title/{id}/")
+}
+
+fn advanced_search_url(base_url: &str, title: &str, year_start: u16, year_end: u16) -> String {
+    format!(
+        "{}/search/title/?title={}&release_date={},{}&adult=include",
+        base_url, title, year_start, year_end
+    )
+}
+
+fn search_url(base_url: &str, title: &str) -> String {
+    format!("{base_url}/find?q={title}")
+}
+
+fn parse_imdb_title_page(
+    response: &str,
+    title_url: String,
+) -> Result<ScrapedIMDbTitlePageData, IMDbScrapeError> {
+    let dom = tl::parse(response, tl::ParserOptions::default()).unwrap();
+    let parser = dom.parser();
+    let genres: Vec<Genre> = {
+        dom.query_selector(".ipc-chip__text")
+            .unwrap()
+            .filter_map(|handle| {
+                let node = handle.get(parser).unwrap();
+                Genre::try_from(&*node.inner_text(parser)).ok()
+            })
+            .collect()
+    };
+
+    if genres.is_empty() {
+        return Err(IMDbScrapeError::GenreParseError {
+            bad_title_url: title_url,
+        });
+    }
 
+    let get_dirty_duration = |nth| {
+        let handle = dom
+            .query_selector(".ipc-inline-list__item")
+            .unwrap()
+            .nth(nth)
+            .expect("Panic occured while trying to export {title} {year}");
+        handle.get(parser).unwrap().inner_text(parser)
+    };
+
+    let mut x = 4;
+    let dirty_duration = {
+        loop {
+            let duration_candidate = get_dirty_duration(x);
+            if IMDb::is_dirty_duration_ok(&duration_candidate) {
+                break duration_candidate;
+            };
+            if x == 7 {
+                return Err(IMDbScrapeError::IrrecoverableParseDurationError {
+                    bad_string: duration_candidate.to_string(),
+                    title_url: title_url.to_string(),
+                });
+            }
+            x += 1;
+        }
+    };
+
+    let duration = IMDb::parse_dirty_duration(&dirty_duration, &title_url)?;
+
+    let title_type = {
+        let page_title = {
+            dom.query_selector("title")
+                .unwrap()
+                .next()
+                .unwrap()
+                .get(parser)
+                .unwrap()
+                .inner_text(parser)
+        };
+        if page_title.contains("TV") && page_title.contains("Series") {
+            TitleType::Show
+        } else if page_title.contains("Video Game") {
+            TitleType::Game
+        } else {
+            TitleType::Movie
+        }
+    };
+
+    Ok(ScrapedIMDbTitlePageData {
+        genres,
+        duration,
+        title_type,
+    })
+}
+
+impl IMDb {
     fn is_dirty_duration_ok(dirty_duration: &str) -> bool {
         if dirty_duration
             .chars()
@@ -223,162 +467,129 @@ impl IMDb {
             Ok(dirty_duration[0])
         }
     }
+}
 
-    pub fn advanced_search(
-        &self,
-        title: &str,
-        year_start: u16,
-        year_end: u16,
-    ) -> Result<IMDbTitle, IMDbScrapeError> {
-        let search_page_url = format!(
-            "https://www.imdb.com/search/title/?title={}&release_date={},{}&adult=include",
-            title, year_start, year_end
-        );
-
-        let search_document = {
-            let response = self.0.get(&search_page_url).send()?.text()?;
-            Html::parse_document(&response)
-        };
-
-        let title_data = if let Some(id) = search_document
-            .select(&Selector::parse("div.lister-item-image").expect("selector ok"))
-            .next()
-        {
-            id
-        } else {
-            log::info!(
+fn parse_advanced_search_page(
+    response: &str,
+    title: &str,
+    year_start: u16,
+    search_page_url: String,
+) -> Result<ScrapedIMDbSearchResult, IMDbScrapeError> {
+    let search_document = Html::parse_document(response);
+
+    let title_data = if let Some(id) = search_document
+        .select(&Selector::parse("div.lister-item-image").expect("selector ok"))
+        .next()
+    {
+        id
+    } else {
+        log::info!(
             "Failed to get a match in Fn get_imdb_data_advanced for {title} {year_start} on {search_page_url}"
         );
-            return Err(IMDbScrapeError::NoResults {
-                search_url: format!("{} {}", title.to_owned(), year_start),
-            });
-        };
-
-        let id = {
-            let id = title_data.inner_html();
-            let regex = Regex::new(r"(\d{7,8})").expect("regex ok");
-            format!("tt{:0>7}", &regex.captures(&id).unwrap()[0])
-                .trim()
-                .to_string()
-        };
-        log::debug!("Found a potential IMDb id for {title} {year_start} on {search_page_url}");
-
-        let title = search_document
-            .select(&Selector::parse("img.loadlate").unwrap())
+        return Err(IMDbScrapeError::NoResults {
+            search_url: format!("{} {}", title.to_owned(), year_start),
+        });
+    };
+
+    let id = {
+        let id = title_data.inner_html();
+        let regex = Regex::new(r"(\d{7,8})").expect("regex ok");
+        format!("tt{:0>7}", &regex.captures(&id).unwrap()[0])
+            .trim()
+            .to_string()
+    };
+    log::debug!("Found a potential IMDb id for {title} {year_start} on {search_page_url}");
+
+    let title = search_document
+        .select(&Selector::parse("img.loadlate").unwrap())
+        .next()
+        .unwrap()
+        .value()
+        .attr("alt")
+        .unwrap();
+
+    // .inner_html gives (2015-2016) / (2015)
+    let year: Year = {
+        let dirty_year = search_document
+            .select(&Selector::parse(".lister-item-year").unwrap())
             .next()
             .unwrap()
-            .value()
-            .attr("alt")
-            .unwrap();
-
-        // .inner_html gives (2015-2016) / (2015)
-        let year: Year = {
-            let dirty_year = search_document
-                .select(&Selector::parse(".lister-item-year").unwrap())
-                .next()
-                .unwrap()
-                .inner_html();
-            match Year::from_str(&dirty_year) {
-                Ok(year) => Ok(year),
-                Err(e) => Err(IMDbScrapeError::IrrecoverableParseYearError {
-                    title_url: search_page_url,
-                    source: e,
-                }),
-            }?
-        };
-
-        let ScrapedIMDbTitlePageData {
-            genres,
-            duration,
-            title_type,
-        } = self.parse_imdb_title_page(&id)?;
-
-        let imdb_data = IMDbTitle {
-            id: TitleID::IMDbID(id),
-            url: OnceCell::new(),
-            year,
-            genres,
-            title: title.to_string(),
-            duration,
-            title_type,
-        };
-
-        Ok(imdb_data)
-    }
-
-    pub fn search(&self, title: &str) -> Result<IMDbTitle, IMDbScrapeError> {
-        let url_query = format!("https://www.imdb.com/find?q={title}");
-        let document = {
-            let response = self.0.get(&url_query).send()?.text()?;
-            Html::parse_document(&response)
-        };
-
-        let title = if let Some(title) = document
-            .select(&Selector::parse(".ipc-metadata-list-summary-item__t").expect("selector ok"))
-            .next()
-        {
-            title.inner_html()
-        } else {
-            return Err(IMDbScrapeError::NoResults {
-                search_url: url_query,
-            });
-        };
-
-        let year = match Year::from_str(
-            &document
-                .select(
-                    &Selector::parse(".ipc-metadata-list-summary-item__li").expect("selector ok"),
-                )
-                .next()
-                .expect("selector is ok")
-                .inner_html(),
-        ) {
-            Ok(year) => year,
-            Err(e) => {
-                return Err(IMDbScrapeError::IrrecoverableParseYearError {
-                    title_url: url_query,
-                    source: e,
-                })
-            }
-        };
+            .inner_html();
+        match Year::from_str(&dirty_year) {
+            Ok(year) => Ok(year),
+            Err(e) => Err(IMDbScrapeError::IrrecoverableParseYearError {
+                title_url: search_page_url,
+                source: e,
+            }),
+        }?
+    };
+
+    Ok(ScrapedIMDbSearchResult {
+        id,
+        title: title.to_string(),
+        year,
+    })
+}
 
-        // Should give something like: /title/tt4158110/?ref_=fn_al_tt_1
-        let dirty_id = document
-            .select(&Selector::parse(".ipc-metadata-list-summary-item__t").expect(""))
+fn parse_search_page(
+    response: &str,
+    url_query: String,
+) -> Result<ScrapedIMDbSearchResult, IMDbScrapeError> {
+    let document = Html::parse_document(response);
+
+    let title = if let Some(title) = document
+        .select(&Selector::parse(".ipc-metadata-list-summary-item__t").expect("selector ok"))
+        .next()
+    {
+        title.inner_html()
+    } else {
+        return Err(IMDbScrapeError::NoResults {
+            search_url: url_query,
+        });
+    };
+
+    let year = match Year::from_str(
+        &document
+            .select(&Selector::parse(".ipc-metadata-list-summary-item__li").expect("selector ok"))
             .next()
+            .expect("selector is ok")
+            .inner_html(),
+    ) {
+        Ok(year) => year,
+        Err(e) => {
+            return Err(IMDbScrapeError::IrrecoverableParseYearError {
+                title_url: url_query,
+                source: e,
+            })
+        }
+    };
+
+    // Should give something like: /title/tt4158110/?ref_=fn_al_tt_1
+    let dirty_id = document
+        .select(&Selector::parse(".ipc-metadata-list-summary-item__t").expect(""))
+        .next()
+        .unwrap()
+        .value()
+        .attr("href")
+        .unwrap(); // TODO: return Outdated err
+    let regex_id = Regex::new(r"(\d{7,8})").unwrap();
+    let id = format!(
+        "tt{:0>7}",
+        regex_id
+            .captures(dirty_id)
             .unwrap()
-            .value()
-            .attr("href")
-            .unwrap(); // TODO: return Outdated err
-        let regex_id = Regex::new(r"(\d{7,8})").unwrap();
-        let id = format!(
-            "tt{:0>7}",
-            regex_id
-                .captures(dirty_id)
-                .unwrap()
-                .get(0)
-                .unwrap()
-                .as_str()
-        );
-
-        let ScrapedIMDbTitlePageData {
-            genres,
-            duration,
-            title_type,
-        } = self.parse_imdb_title_page(&id)?;
+            .get(0)
+            .unwrap()
+            .as_str()
+    );
 
-        let imdb_data = IMDbTitle {
-            id: TitleID::IMDbID(id),
-            url: OnceCell::new(),
-            year,
-            genres,
-            title_type,
-            title,
-            duration,
-        };
+    Ok(ScrapedIMDbSearchResult { id, title, year })
+}
 
-        Ok(imdb_data)
-    }
+struct ScrapedIMDbSearchResult {
+    id: String,
+    title: String,
+    year: Year,
 }
 
 struct ScrapedIMDbTitlePageData {
@@ -390,15 +601,22 @@ struct ScrapedIMDbTitlePageData {
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "async")]
+    use crate::http::testing::async_cassette;
+    use crate::http::testing::cassette;
 
     #[test]
     fn searching_imdb() {
-        let imdb = IMDb::new();
+        let imdb = IMDb::builder()
+            .transport(cassette("searching_imdb"))
+            .build()
+            .unwrap();
         let stay = imdb.search("Stay 2005").unwrap();
         assert_eq!(stay.title(), "Zostań"); // why the hell this is in polish for me TODO ?
         assert_eq!(stay.year().start(), 2005);
         assert_eq!(stay.year().end(), 2005);
         assert_eq!(stay.duration(), Some(99));
+        assert_eq!(stay.url(), "https://www.imdb.com/title/tt0371257/");
         assert_eq!(
             *stay.genres(),
             vec![Genre::Drama, Genre::Mystery, Genre::Thriller]
@@ -407,18 +625,35 @@ mod tests {
 
     #[test]
     fn searching_imdb2() {
-        let imdb = IMDb::new();
+        let imdb = IMDb::builder()
+            .transport(cassette("searching_imdb2"))
+            .build()
+            .unwrap();
         let movie = imdb.search("Josee to Tora to Sakana-tachi").unwrap();
         assert_eq!(movie.year().start(), 2020);
     }
 
     #[test]
     fn searching_imdb3() {
-        let imdb = IMDb::new();
+        let imdb = IMDb::builder()
+            .transport(cassette("searching_imdb3"))
+            .build()
+            .unwrap();
         let the_whale = imdb.search("The Whale 2022").unwrap();
         assert_eq!(the_whale.year().start(), 2022);
     }
 
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn searching_imdb_async() {
+        let imdb = IMDb::builder()
+            .async_transport(async_cassette("searching_imdb_async"))
+            .build_async()
+            .unwrap();
+        let the_whale = imdb.search("The Whale 2022").await.unwrap();
+        assert_eq!(the_whale.year().start(), 2022);
+    }
+
     #[test]
     fn parsing_dirty_duration() {
         let first = IMDb::parse_dirty_duration("2h", "2h test").unwrap();
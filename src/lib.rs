@@ -3,6 +3,10 @@
 //! Unofficial API to query and interact with filmweb.pl from Rust with support for authentication
 //! with cookies.
 //! Highly prone to breaking changes.
+//!
+//! # Features
+//! - `async` - async flavours of the scrapers: `AsyncFilmweb`, `AsyncFilmwebUser` and `AsyncIMDb`
+//! - `encrypted-store` - `EncryptedFileStore`, a passphrase-encrypted file of Filmweb cookies
 
 /// Error types
 pub mod error;
@@ -10,20 +14,28 @@ pub mod error;
 /// `Filmweb` api
 pub mod filmweb;
 
+/// Pluggable HTTP transport
+pub mod http;
+
 /// `IMDb` api
 pub mod imdb;
 
 mod utils;
 
+#[cfg(feature = "async")]
+use std::future::Future;
 use std::{
     fmt::{self, Display},
     str::FromStr,
 };
 
 use error::{FilmwebErrors, ParseYearError};
+#[cfg(feature = "async")]
+use imdb::AsyncIMDb;
 use imdb::IMDb;
 use priority_queue::PriorityQueue;
 use serde::{Deserialize, Serialize};
+use time::{Date, OffsetDateTime};
 
 const USER_AGENT: &str =
     "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:106.0) Gecko/20100101 Firefox/108.0";
@@ -32,6 +44,10 @@ pub trait User {
     fn username(&self) -> &String;
     fn num_of_rated_movies(&self) -> u16;
     fn num_of_rated_shows(&self) -> u16;
+    /// Zero unless the site rates games as well
+    fn num_of_rated_games(&self) -> u16 {
+        0
+    }
     fn num_of_watchlisted_titles(&self) -> u16;
 }
 
@@ -157,6 +173,7 @@ impl Display for Year {
 pub enum TitleType {
     Movie,
     Show,
+    Game,
 }
 
 #[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -225,6 +242,15 @@ pub trait Title {
 
     fn duration(&self) -> Option<u16>;
 
+    /// Async flavour of [`Title::duration`], for titles that fetch it the first time it's needed
+    #[cfg(feature = "async")]
+    fn duration_async(&self) -> impl Future<Output = Option<u16>> + Send
+    where
+        Self: Sync,
+    {
+        async move { self.duration() }
+    }
+
     fn genres(&self) -> &Vec<Genre>;
 
     fn year(&self) -> Year;
@@ -243,7 +269,7 @@ pub trait Title {
     ///
     /// # Examples
     ///
-    /// ```rust
+    /// ```rust,no_run
     /// # use std::error::Error;
     /// #
     /// # fn main() -> Result<(), Box<dyn Error>> {
@@ -286,6 +312,18 @@ pub trait Title {
 
 pub trait AlternateTitles: Title {
     fn alter_titles(&mut self) -> Option<&mut PriorityQueue<AlternateTitle, u8>>;
+
+    /// Async flavour of [`AlternateTitles::alter_titles`], for titles that fetch them the first
+    /// time they're needed
+    #[cfg(feature = "async")]
+    fn alter_titles_async(
+        &mut self,
+    ) -> impl Future<Output = Option<&mut PriorityQueue<AlternateTitle, u8>>> + Send
+    where
+        Self: Send,
+    {
+        async move { self.alter_titles() }
+    }
 }
 
 pub trait IMDbLookup: Title + AlternateTitles {
@@ -301,34 +339,80 @@ pub trait IMDbLookup: Title + AlternateTitles {
         };
 
         // Will check until there's a good canditate. Break on score == 0 when it takes too long
-        while let Some((ref alternate_title, _score)) = self.alter_titles().as_mut().unwrap().pop()
+        while let Some((ref alternate_title, _score)) =
+            self.alter_titles().and_then(PriorityQueue::pop)
         {
             let advanced_search = imdb.advanced_search(&alternate_title.title, year, year);
             if let Ok(imdb_title) = advanced_search {
-                if self.is_duration_similar(imdb_title.duration().unwrap() as u32)
-                    && self.is_year_similar(imdb_title.year())
-                {
+                if is_imdb_candidate(self, &imdb_title) {
                     return Ok(imdb_title);
                 };
             }
 
             let normal_search = imdb.search(&format!("{} {}", &alternate_title.title, self.year()));
             if let Ok(imdb_title) = normal_search {
-                if self.is_duration_similar(imdb_title.duration().unwrap() as u32)
-                    && self.is_year_similar(imdb_title.year())
-                {
+                if is_imdb_candidate(self, &imdb_title) {
                     return Ok(imdb_title);
                 };
             }
         }
         Err(FilmwebErrors::ZeroResults)
     }
+
+    /// Async flavour of [`IMDbLookup::imdb_lookup`]
+    #[cfg(feature = "async")]
+    fn imdb_lookup_async<'a>(
+        &'a mut self,
+        imdb: &'a AsyncIMDb,
+    ) -> impl Future<Output = Result<imdb::IMDbTitle, FilmwebErrors>> + Send + 'a
+    where
+        Self: Send + Sync,
+    {
+        async move {
+            let year = self.year().start();
+            // Candidates are compared by duration, which may have to be fetched first
+            self.duration_async().await;
+
+            while let Some((ref alternate_title, _score)) =
+                self.alter_titles_async().await.and_then(PriorityQueue::pop)
+            {
+                let advanced_search = imdb
+                    .advanced_search(&alternate_title.title, year, year)
+                    .await;
+                if let Ok(imdb_title) = advanced_search {
+                    if is_imdb_candidate(self, &imdb_title) {
+                        return Ok(imdb_title);
+                    };
+                }
+
+                let normal_search = imdb
+                    .search(&format!("{} {}", &alternate_title.title, self.year()))
+                    .await;
+                if let Ok(imdb_title) = normal_search {
+                    if is_imdb_candidate(self, &imdb_title) {
+                        return Ok(imdb_title);
+                    };
+                }
+            }
+            Err(FilmwebErrors::ZeroResults)
+        }
+    }
+}
+
+/// Returns true if an `IMDb` search result is similar enough to be the looked up title
+fn is_imdb_candidate<T: Title + ?Sized>(title: &T, imdb_title: &imdb::IMDbTitle) -> bool {
+    title.is_duration_similar(u32::from(imdb_title.duration().unwrap()))
+        && title.is_year_similar(imdb_title.year())
 }
 
 pub trait RatedTitle: Title {
     fn rating(&self) -> Option<u8>;
     fn is_favorited(&self) -> bool;
     fn is_watchlisted(&self) -> bool;
+    /// When the title was last rated, `None` for watchlisted titles
+    fn rated_at(&self) -> Option<OffsetDateTime>;
+    /// Day the user says they saw the title on, if they gave one
+    fn view_date(&self) -> Option<Date>;
 }
 
 #[cfg(test)]
@@ -1,38 +1,14 @@
-use crate::USER_AGENT;
+use crate::error::BuildError;
+use crate::http::ClientOptions;
 use reqwest::blocking::Client;
 use reqwest::header;
-use std::ops::Deref;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
-#[derive(Debug)]
-pub struct ClientPool {
-    clients: Vec<Client>,
-}
-
-impl ClientPool {
-    pub fn new(client_sample: Client, amount: u8) -> Self {
-        log::debug!("Creating a ClientPool");
-        let mut clients = Vec::new();
-        for _ in 0..amount - 1 {
-            clients.push(client_sample.clone());
-        }
-        clients.push(client_sample);
-
-        Self { clients }
-    }
-}
-
-impl Deref for ClientPool {
-    type Target = Client;
-
-    fn deref(&self) -> &Self::Target {
-        let i = fastrand::usize(..self.clients.len());
-        &self.clients[i]
-    }
-}
-
-/// Creates a Reqwest HTTP client with additional headers
-pub fn create_client() -> Result<Client, reqwest::Error> {
-    log::debug!("Creating a Client");
+/// Headers sent with every request, shared by the blocking and the async client. Extra headers
+/// of `options` replace the default ones
+fn default_headers(options: &ClientOptions) -> Result<header::HeaderMap, BuildError> {
     let mut headers = header::HeaderMap::new();
 
     headers.insert(
@@ -51,10 +27,203 @@ pub fn create_client() -> Result<Client, reqwest::Error> {
         header::HeaderValue::from_static("pl_PL"),
     );
 
-    Client::builder()
-        .user_agent(USER_AGENT)
+    for (name, value) in &options.headers {
+        let invalid = || BuildError::InvalidHeader { name: name.clone() };
+        headers.insert(
+            header::HeaderName::from_bytes(name.as_bytes()).map_err(|_| invalid())?,
+            header::HeaderValue::from_str(value).map_err(|_| invalid())?,
+        );
+    }
+
+    Ok(headers)
+}
+
+/// Parses every PEM bundle, each has to hold at least one certificate
+fn root_certificates(options: &ClientOptions) -> Result<Vec<reqwest::Certificate>, BuildError> {
+    let mut certificates = Vec::new();
+    for pem in &options.root_certificates {
+        let bundle = reqwest::Certificate::from_pem_bundle(pem)?;
+        if bundle.is_empty() {
+            return Err(BuildError::InvalidCertificate);
+        }
+        certificates.extend(bundle);
+    }
+    Ok(certificates)
+}
+
+/// Creates a Reqwest HTTP client with additional headers. It keeps no cookies, requests of
+/// logged in users carry their own `Cookie` header and anonymous ones none
+pub fn create_client(options: &ClientOptions) -> Result<Client, BuildError> {
+    log::debug!("Creating a Client");
+    let mut builder = Client::builder()
+        .user_agent(&options.user_agent)
         .gzip(true)
-        .default_headers(headers)
-        .cookie_store(true)
-        .build()
+        .default_headers(default_headers(options)?)
+        .local_address(options.local_address)
+        .timeout(options.timeout)
+        .tls_built_in_root_certs(options.built_in_root_certificates);
+    if let Some(timeout) = options.connect_timeout {
+        builder = builder.connect_timeout(timeout);
+    }
+    if let Some(proxy) = &options.proxy {
+        builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+    }
+    for certificate in root_certificates(options)? {
+        builder = builder.add_root_certificate(certificate);
+    }
+    Ok(builder.build()?)
+}
+
+/// Creates an async Reqwest HTTP client with the same setup as [`create_client`]
+#[cfg(feature = "async")]
+pub fn create_async_client(options: &ClientOptions) -> Result<reqwest::Client, BuildError> {
+    log::debug!("Creating an async Client");
+    let mut builder = reqwest::Client::builder()
+        .user_agent(&options.user_agent)
+        .gzip(true)
+        .default_headers(default_headers(options)?)
+        .local_address(options.local_address)
+        .tls_built_in_root_certs(options.built_in_root_certificates);
+    if let Some(timeout) = options.timeout {
+        builder = builder.timeout(timeout);
+    }
+    if let Some(timeout) = options.connect_timeout {
+        builder = builder.connect_timeout(timeout);
+    }
+    if let Some(proxy) = &options.proxy {
+        builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+    }
+    for certificate in root_certificates(options)? {
+        builder = builder.add_root_certificate(certificate);
+    }
+    Ok(builder.build()?)
+}
+
+/// Validates a base url and strips its trailing slashes, so paths can be appended to it
+pub fn parse_base_url(base_url: &str) -> Result<String, BuildError> {
+    let url = url::Url::parse(base_url).map_err(|source| BuildError::InvalidBaseUrl {
+        url: base_url.to_owned(),
+        source,
+    })?;
+    if url.cannot_be_a_base() || url.query().is_some() {
+        return Err(BuildError::NotABaseUrl {
+            url: base_url.to_owned(),
+        });
+    }
+    Ok(base_url.trim_end_matches('/').to_owned())
+}
+
+/// Replaces the file at `path` with `contents`, readable by its owner only on unix as it holds
+/// secrets. The contents go to a temporary file next to it first, so a failed write leaves the
+/// old file in place
+pub fn write_private_file(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(dir)?;
+    let temp = temp_path(path);
+    let written = write_synced(&temp, contents).and_then(|()| fs::rename(&temp, path));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(e);
+    }
+    // The rename is only durable once the directory is synced too
+    #[cfg(unix)]
+    fs::File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+/// File the new contents of `path` are written to before they replace the old ones, in the same
+/// directory so that the rename doesn't cross filesystems
+pub fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.tmp", std::process::id()));
+    path.with_file_name(name)
+}
+
+/// Writes `contents` to a file readable by its owner only and flushes them to disk. The
+/// permissions are set explicitly, as a file left over by a failed write keeps its old ones
+fn write_synced(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// Minimal HTTP server answering with canned bodies, to test scrapers against a base url
+#[cfg(test)]
+pub mod mock_server {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Serves the body of the first route whose path is a prefix of the requested one (404
+    /// otherwise) and returns the server's base url
+    pub fn serve(routes: Vec<(&'static str, &'static str)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("can bind a port");
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut buffer = [0; 8192];
+                let read = stream.read(&mut buffer).unwrap_or(0);
+                let request = String::from_utf8_lossy(&buffer[..read]);
+                let path = request.split_whitespace().nth(1).unwrap_or("/");
+                let response = routes.iter().find(|(route, _)| path.starts_with(route)).map_or_else(
+                    || "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_owned(),
+                    |(_, body)| {
+                        format!(
+                            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                            body.len()
+                        )
+                    },
+                );
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        base_url
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parsing_base_url() {
+        assert_eq!(
+            parse_base_url("http://127.0.0.1:8080/").unwrap(),
+            "http://127.0.0.1:8080"
+        );
+        assert_eq!(
+            parse_base_url("https://proxy.local/filmweb").unwrap(),
+            "https://proxy.local/filmweb"
+        );
+        assert!(parse_base_url("www.filmweb.pl").is_err());
+        assert!(parse_base_url("mailto:someone@filmweb.pl").is_err());
+    }
+
+    #[test]
+    fn creating_client_with_options() {
+        let options = ClientOptions::new()
+            .proxy("http://127.0.0.1:3128")
+            .user_agent("filmed")
+            .header("x-locale", "en_US")
+            .timeout(None);
+        assert!(create_client(&options).is_ok());
+        assert!(matches!(
+            create_client(&ClientOptions::new().header("bad header", "value")),
+            Err(BuildError::InvalidHeader { .. })
+        ));
+        assert!(create_client(&ClientOptions::new().header("x-locale", "pl\n")).is_err());
+        assert!(matches!(
+            create_client(&ClientOptions::new().root_certificate("not a certificate")),
+            Err(BuildError::InvalidCertificate)
+        ));
+    }
 }